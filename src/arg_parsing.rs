@@ -1,24 +1,44 @@
+use crate::completions;
+use crate::man;
+use crate::shell;
+use chrono::{Local, NaiveDate};
 use clap::Parser;
 use clap::Subcommand;
-use std::path::Path;
+use clap_complete::Shell;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use todo_rs::board::Columns;
 use todo_rs::command::*;
+use todo_rs::config::Config;
+use todo_rs::estimate::Estimate;
+use todo_rs::export::ExportFormat;
+use todo_rs::stats::OutputFormat;
+use todo_rs::timelog::Grouping;
+use todo_rs::Error;
+use todo_rs::Status;
+use todo_rs::TaskRef;
 
 /// Simple todo lists
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
     #[clap(subcommand)]
-    command: Commands,
+    pub(crate) command: Commands,
+    /// Use the named list in .todo.d/ or <LIST>.todo instead of .todo
+    #[clap(long, global = true, value_parser)]
+    pub(crate) list: Option<String>,
 }
 
 impl Args {
     pub fn execute_command(&self) {
-        self.command.execute();
+        self.command
+            .execute(self.list.as_deref())
+            .unwrap_or_else(|error| exit_with_error(error));
     }
 }
 
 #[derive(Debug, Subcommand)]
-enum Commands {
+pub(crate) enum Commands {
     /// Create a new todo list
     Create,
     /// Add an item to the todo list
@@ -26,48 +46,635 @@ enum Commands {
         /// Text of the new item
         #[clap(value_parser)]
         text: String,
+        /// Add the item even if a similar one is already on the list
+        #[clap(long, short)]
+        force: bool,
     },
     /// List all items on the list
-    List,
+    List {
+        /// List all lists below the current directory, skipping files ignored by git
+        #[clap(long, short)]
+        recursive: bool,
+        /// Merge all lists into one, addressable with `path:index`
+        #[clap(long, requires = "recursive")]
+        flat: bool,
+        /// Show the dates, when the items were created and completed
+        #[clap(long)]
+        dates: bool,
+        /// Sum up the `est:` estimates per status and per tag
+        #[clap(long, conflicts_with = "recursive")]
+        estimates: bool,
+    },
+    /// Pick open items by priority and due date, until they fill the capacity
+    Plan {
+        /// Time or points available, e.g. `6h` or `8`
+        #[clap(long, value_parser)]
+        capacity: Estimate,
+    },
     /// Mark an item on the list as done
     Check {
-        /// Index of the item to check
+        /// Index of the item to check, or `path:index` for an item on another list
+        #[clap(value_parser)]
+        item: TaskRef,
+        /// Record the commit checked out in git as the one, that finished the item
+        #[clap(long)]
+        commit: bool,
+    },
+    /// Mark an item as open again
+    #[clap(visible_alias = "reopen")]
+    Uncheck {
+        /// Index of the item to uncheck
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Mark an item as cancelled, it's closed without being done
+    Cancel {
+        /// Index of the item to cancel
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Mark an item as blocked
+    Block {
+        /// Index of the item to block
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Mark an item as waiting, e.g. for someone else
+    Wait {
+        /// Index of the item, that waits
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Show the items as a board with a column per status
+    Board {
+        /// Make a column per status or per tag
+        #[clap(long, value_parser, default_value = "status")]
+        by: Columns,
+        /// Width of the board [default: width of the terminal]
+        #[clap(long, value_parser)]
+        width: Option<usize>,
+    },
+    /// Move an item to another column of the board, e.g. `done` or `in-progress`
+    MoveStatus {
+        /// Index of the item to move
+        #[clap(value_parser)]
+        item_index: usize,
+        /// Status to move the item to
+        #[clap(value_parser)]
+        column: String,
+    },
+    /// Change the text of an item
+    Edit {
+        /// Index of the item to edit
+        #[clap(value_parser)]
+        item_index: usize,
+        /// New text of the item
+        #[clap(value_parser)]
+        text: String,
+    },
+    /// Edit the notes of an item in $EDITOR
+    Note {
+        /// Index of the item to take notes on
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Print an item and the items it has to wait for as a tree
+    Deps {
+        /// Index of the item to show the dependencies of
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Print an item with its dates and notes
+    Show {
+        /// Index of the item to show
         #[clap(value_parser)]
         item_index: usize,
     },
     /// Remove an item from the list
     Remove {
-        /// Index of the item to remove
+        /// Index of the item to remove, or `path:index` for an item on another list
+        #[clap(value_parser)]
+        item: TaskRef,
+    },
+    /// Move an item to a new position among the items with the same status
+    Move {
+        /// Index of the item to move
+        #[clap(value_parser)]
+        item_index: usize,
+        /// Index the item should have afterwards
+        #[clap(value_parser)]
+        new_index: usize,
+    },
+    /// Move an item to the top of the list
+    Top {
+        /// Index of the item to move
         #[clap(value_parser)]
         item_index: usize,
     },
+    /// Move an item to the bottom of the list
+    Bottom {
+        /// Index of the item to move
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Move an item to another list
+    Mv {
+        /// Index of the item to move
+        #[clap(value_parser)]
+        item_index: usize,
+        /// Name of the list to move the item to
+        #[clap(long, value_parser)]
+        to: String,
+    },
+    /// Show all lists in this directory
+    Lists,
+    /// Add TODO, FIXME and XXX comments from source files as items
+    Scan {
+        /// Files or directories to scan
+        #[clap(value_parser, default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Add all new comments without asking
+        #[clap(long, short)]
+        yes: bool,
+    },
+    /// Update items added by scan to the current state of their comments
+    SyncCode {
+        /// Remove the comments of checked items from the source files
+        #[clap(long)]
+        remove_comments: bool,
+    },
+    /// Show statistics about the list
+    Stats {
+        /// Only count items added or completed on or after this date (YYYY-MM-DD)
+        #[clap(long, value_parser)]
+        since: Option<NaiveDate>,
+        /// Output format: text or json
+        #[clap(long, value_parser, default_value = "text")]
+        output: OutputFormat,
+        /// Read the items from this file instead of the list, e.g. an archive
+        #[clap(long, value_parser)]
+        file: Option<PathBuf>,
+    },
+    /// Export the list as CSV, HTML, Markdown or iCalendar
+    Export {
+        /// Output format: csv, html, markdown or ics
+        #[clap(long, short, value_parser)]
+        format: ExportFormat,
+        /// Write to this file instead of stdout
+        #[clap(long, short, value_parser)]
+        output: Option<PathBuf>,
+    },
+    /// Import the tasks of an iCalendar (.ics) file
+    Import {
+        #[clap(value_parser)]
+        file: PathBuf,
+    },
+    /// Merge duplicate items, keeping the earliest one
+    Dedupe,
+    /// Mark an item as in progress and track the time spent on it, stopping the item tracked so far
+    Start {
+        /// Index of the item to work on
+        #[clap(value_parser)]
+        item_index: usize,
+    },
+    /// Stop tracking time
+    Stop,
+    /// Show all tracked time intervals
+    Log,
+    /// Summarize the list
+    Report {
+        #[clap(subcommand)]
+        report: Report,
+    },
+    /// Open the list in an interactive terminal interface
+    #[clap(short_flag = 'i')]
+    Ui,
+    /// Start a prompt to run several commands on the list
+    Shell,
+    /// Check items from commit messages
+    GitHook {
+        #[clap(subcommand)]
+        hook: GitHook,
+    },
+    /// Merge the versions of a list, run by git when merging branches
+    MergeDriver {
+        /// The common ancestor (%O)
+        #[clap(value_parser)]
+        base: PathBuf,
+        /// Our version, replaced by the merge (%A)
+        #[clap(value_parser)]
+        ours: PathBuf,
+        /// Their version (%B)
+        #[clap(value_parser)]
+        theirs: PathBuf,
+    },
+    /// Register the merge driver in .gitattributes and the local git config
+    GitSetup,
+    /// Show the items added, removed, completed, reopened and edited between git revisions
+    Diff {
+        /// `<old>..<new>`, or `<old>` to compare with the working tree [default: HEAD]
+        #[clap(value_parser)]
+        range: Option<String>,
+    },
+    /// Print the completion script for a shell
+    Completions {
+        /// Shell to print the script for
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+    /// Write man pages for todo and all subcommands
+    Man {
+        /// Directory to write the pages to
+        #[clap(long, value_parser, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// Print the open items as `index<TAB>text`, used by the completion scripts
+    #[clap(hide = true)]
+    CompleteItems,
     /// Destroy the todo list
     Destroy,
 }
 
+#[derive(Debug, Subcommand)]
+pub(crate) enum Report {
+    /// Sum up the tracked time
+    Time {
+        /// Group the time by day, task or tag
+        #[clap(long, value_parser, default_value = "task")]
+        by: Grouping,
+    },
+    /// Compare the estimates of done items with their tracked time
+    Estimates,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum GitHook {
+    /// Install a commit-msg hook, that checks the items referenced by `Todo: #<index>` trailers
+    Install,
+    /// Check the items referenced in a commit message, run by the hook
+    #[clap(hide = true)]
+    CommitMsg {
+        #[clap(value_parser)]
+        message_file: PathBuf,
+    },
+}
+
 impl Commands {
-    pub fn execute(&self) {
-        // TODO: add a Result as return value
+    pub fn execute(&self, list_name: Option<&str>) -> Result<(), Error> {
+        if let Some(name) = list_name {
+            check_list_name(name)?;
+        }
+        let file_path = list_path(Path::new("."), list_name);
+        let file_path = file_path.as_path();
+        let now = Local::now().naive_local();
+        let today = now.date();
+        let config = Config::load(Path::new("."));
+        let markers = &config.markers;
         match self {
-            Commands::Create => match create(Path::new(".")) {
-                Ok(_) => println!("created a new .todo file"),
-                Err(_) => println!("Warning: \".todo\" already exists. Quitting"),
+            Commands::Create => match (create(Path::new("."), list_name), list_name) {
+                (Ok(_), None) => println!("created a new .todo file"),
+                (Ok(_), Some(name)) => println!("created a new list \"{}\"", name),
+                (Err(_), None) => println!("Warning: \".todo\" already exists. Quitting"),
+                (Err(_), Some(name)) => {
+                    println!("Warning: list \"{}\" already exists. Quitting", name)
+                }
+            },
+            Commands::Add { text, force } => {
+                let threshold = match force {
+                    true => None,
+                    false => Some(config.similarity_threshold),
+                };
+                match add(
+                    file_path,
+                    markers,
+                    text,
+                    today,
+                    threshold,
+                    &mut std::io::stdout(),
+                ) {
+                    Ok(_) => {}
+                    Err(Error::Duplicate(task)) if confirm_duplicate(&task) => {
+                        add(
+                            file_path,
+                            markers,
+                            text,
+                            today,
+                            None,
+                            &mut std::io::stdout(),
+                        )
+                        .unwrap();
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+            Commands::List {
+                recursive,
+                flat,
+                dates,
+                estimates,
+            } => match recursive {
+                true => list_recursive(Path::new("."), *flat, *dates, &mut std::io::stdout())?,
+                false => {
+                    list(file_path, markers, *dates, &mut std::io::stdout())?;
+                    if *estimates {
+                        list_estimates(file_path, markers, &mut std::io::stdout())?;
+                    }
+                }
             },
-            Commands::Add { text } => {
-                add(Path::new(".todo"), text, &mut std::io::stderr());
+            Commands::Plan { capacity } => {
+                plan(file_path, markers, *capacity, &mut std::io::stdout())?;
+            }
+            Commands::Check { item, commit } => {
+                let file_path = item.path.as_deref().unwrap_or(file_path);
+                let commit = match commit {
+                    true => Some(todo_rs::git::head(Path::new("."))?),
+                    false => None,
+                };
+                check(
+                    file_path,
+                    markers,
+                    item.index,
+                    now,
+                    commit.as_deref(),
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Uncheck { item_index } => {
+                uncheck(file_path, markers, *item_index, &mut std::io::stdout())?;
+            }
+            Commands::Cancel { item_index } => {
+                let status = Status::Cancelled;
+                set_status(
+                    file_path,
+                    markers,
+                    *item_index,
+                    status,
+                    now,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Block { item_index } => {
+                let status = Status::Blocked;
+                set_status(
+                    file_path,
+                    markers,
+                    *item_index,
+                    status,
+                    now,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Wait { item_index } => {
+                let status = Status::Waiting;
+                set_status(
+                    file_path,
+                    markers,
+                    *item_index,
+                    status,
+                    now,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Board { by, width } => {
+                let width = width.unwrap_or_else(terminal_width);
+                board(file_path, markers, *by, width, &mut std::io::stdout())?;
+            }
+            Commands::MoveStatus { item_index, column } => {
+                let status = markers
+                    .status(column)
+                    .ok_or_else(|| Error::UnknownStatus(column.clone()))?;
+                move_status(
+                    file_path,
+                    markers,
+                    *item_index,
+                    status,
+                    now,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Edit { item_index, text } => {
+                edit(
+                    file_path,
+                    markers,
+                    *item_index,
+                    text,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Note { item_index } => {
+                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+                note(
+                    file_path,
+                    markers,
+                    *item_index,
+                    &editor,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Deps { item_index } => {
+                deps(file_path, markers, *item_index, &mut std::io::stdout())?;
+            }
+            Commands::Show { item_index } => {
+                show(file_path, markers, *item_index, &mut std::io::stdout())?;
+            }
+            Commands::Remove { item } => {
+                let file_path = item.path.as_deref().unwrap_or(file_path);
+                remove(file_path, markers, item.index, &mut std::io::stdout())?;
+            }
+            Commands::Move {
+                item_index,
+                new_index,
+            } => {
+                move_task(
+                    file_path,
+                    markers,
+                    *item_index,
+                    *new_index,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Top { item_index } => {
+                move_task(file_path, markers, *item_index, 1, &mut std::io::stdout())?;
             }
-            Commands::List => {
-                list(Path::new(".todo"), &mut std::io::stdout());
+            Commands::Bottom { item_index } => {
+                move_task(
+                    file_path,
+                    markers,
+                    *item_index,
+                    usize::MAX,
+                    &mut std::io::stdout(),
+                )?;
             }
-            Commands::Check { item_index } => {
-                check(Path::new(".todo"), *item_index, &mut std::io::stdout());
+            Commands::Mv { item_index, to } => {
+                check_list_name(to)?;
+                let target_path = list_path(Path::new("."), Some(to));
+                move_to_list(
+                    file_path,
+                    markers,
+                    *item_index,
+                    &target_path,
+                    &mut std::io::stdout(),
+                )?;
             }
-            Commands::Remove { item_index } => {
-                remove(Path::new(".todo"), *item_index, &mut std::io::stdout());
+            Commands::Lists => {
+                lists(Path::new("."), markers, &mut std::io::stdout())?;
+            }
+            Commands::Scan { paths, yes } => {
+                scan(
+                    file_path,
+                    markers,
+                    paths,
+                    today,
+                    *yes,
+                    &mut std::io::stdin().lock(),
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::SyncCode { remove_comments } => {
+                sync_code(
+                    file_path,
+                    markers,
+                    *remove_comments,
+                    today,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Stats {
+                since,
+                output,
+                file,
+            } => {
+                let file_path = file.as_deref().unwrap_or(file_path);
+                stats(file_path, markers, *since, *output, &mut std::io::stdout())?;
+            }
+            Commands::Export { format, output } => {
+                let title = list_name.unwrap_or("todo");
+                let result = match output {
+                    Some(output) => {
+                        let mut file = std::fs::File::create(output)
+                            .map_err(|_| Error::CannotCreate(output.display().to_string()))?;
+                        export(file_path, markers, *format, title, &mut file)
+                    }
+                    None => export(file_path, markers, *format, title, &mut std::io::stdout()),
+                };
+                result?;
+            }
+            Commands::Import { file } => {
+                import(file_path, markers, file, &mut std::io::stdout())?;
+            }
+            Commands::Dedupe => {
+                dedupe(
+                    file_path,
+                    markers,
+                    config.similarity_threshold,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Start { item_index } => {
+                start(file_path, markers, *item_index, now, &mut std::io::stdout())?;
+            }
+            Commands::Stop => {
+                stop(file_path, markers, now, &mut std::io::stdout())?;
+            }
+            Commands::Log => {
+                log(file_path, markers, now, &mut std::io::stdout())?;
+            }
+            Commands::Report {
+                report: Report::Time { by },
+            } => {
+                report_time(file_path, markers, *by, now, &mut std::io::stdout())?;
+            }
+            Commands::Report {
+                report: Report::Estimates,
+            } => {
+                report_estimates(file_path, markers, now, &mut std::io::stdout())?;
+            }
+            Commands::Ui => {
+                todo_rs::ui::run(file_path, &config, today)?;
+            }
+            Commands::Shell => {
+                shell::run(list_name)?;
+            }
+            Commands::GitHook {
+                hook: GitHook::Install,
+            } => {
+                let executable =
+                    std::env::current_exe().expect("Couldn't find the todo executable");
+                install_hook(
+                    Path::new("."),
+                    &executable,
+                    list_name,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::GitHook {
+                hook: GitHook::CommitMsg { message_file },
+            } => {
+                commit_msg(
+                    file_path,
+                    markers,
+                    message_file,
+                    now,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::MergeDriver { base, ours, theirs } => {
+                merge_driver(base, ours, theirs, markers)?;
+            }
+            Commands::GitSetup => {
+                let executable =
+                    std::env::current_exe().expect("Couldn't find the todo executable");
+                git_setup(Path::new("."), &executable, &mut std::io::stdout())?;
+            }
+            Commands::Diff { range } => {
+                diff(
+                    Path::new("."),
+                    file_path,
+                    range.as_deref(),
+                    markers,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            Commands::Completions { shell } => {
+                completions::generate(*shell, &mut std::io::stdout());
+            }
+            Commands::Man { out_dir } => {
+                man::generate(out_dir, &mut std::io::stdout());
+            }
+            Commands::CompleteItems => {
+                complete_items(file_path, markers, &mut std::io::stdout());
             }
             Commands::Destroy => {
-                destroy(Path::new(".todo"), &mut std::io::stdout());
+                destroy(file_path, &mut std::io::stdout());
             }
         }
+        Ok(())
+    }
+}
+
+/// Width of the terminal, or 80 if the output doesn't go to one
+fn terminal_width() -> usize {
+    match std::io::stdout().is_terminal() {
+        true => ratatui::crossterm::terminal::size().map_or(80, |(width, _)| width as usize),
+        false => 80,
+    }
+}
+
+fn exit_with_error(error: Error) -> ! {
+    eprintln!("Error: {}", error);
+    std::process::exit(1);
+}
+
+/// Asks the user whether to add a task anyway. Never asks if stdin isn't a terminal.
+pub(crate) fn confirm_duplicate(duplicate: &todo_rs::Task) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
     }
+    eprint!(
+        "A similar item is already on the list: {}\nAdd anyway? [y/N] ",
+        duplicate.without_dates()
+    );
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap();
+    answer.trim().eq_ignore_ascii_case("y")
 }
@@ -0,0 +1,272 @@
+use crate::{ParseError, Status, Task, TaskList};
+use std::str::FromStr;
+
+/// Columns are never narrower than this, even if the board gets wider than
+/// the terminal
+const MIN_COLUMN_WIDTH: usize = 12;
+
+/// Between two columns
+const SEPARATOR: &str = " │ ";
+
+/// Statuses, that always get a column, even without items
+const DEFAULT_COLUMNS: [Status; 3] = [Status::InProgress, Status::Unfinished, Status::Finished];
+
+/// What `todo board` makes a column for
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Columns {
+    Status,
+    Tag,
+}
+
+impl FromStr for Columns {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        match input {
+            "status" => Ok(Columns::Status),
+            "tag" => Ok(Columns::Tag),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Column {
+    title: String,
+    /// `index text` of the items in the column
+    cards: Vec<String>,
+}
+
+fn column<'a>(columns: &'a mut Vec<Column>, title: &str) -> &'a mut Column {
+    match columns.iter().position(|column| column.title == title) {
+        Some(position) => &mut columns[position],
+        None => {
+            columns.push(Column {
+                title: title.to_string(),
+                cards: vec![],
+            });
+            columns.last_mut().unwrap()
+        }
+    }
+}
+
+/// A column per status, in the order of the listing
+fn by_status(task_list: &TaskList) -> Vec<Column> {
+    let mut statuses: Vec<&Status> = DEFAULT_COLUMNS.iter().collect();
+    for task in task_list.tasks.iter() {
+        let status = task_list.displayed_status(task);
+        if !statuses.contains(&status) {
+            statuses.push(status);
+        }
+    }
+    statuses.sort_by_key(|status| status.rank());
+    let mut columns: Vec<Column> = vec![];
+    for status in statuses {
+        column(&mut columns, &status.name());
+    }
+    for (index, position) in task_list.display_order().into_iter().enumerate() {
+        let task = &task_list.tasks[position];
+        let status = task_list.displayed_status(task);
+        // the column already tells the status
+        let line = task
+            .without_dates()
+            .display_with(&task_list.markers)
+            .to_string();
+        let marker = task_list.markers.marker(&task.status);
+        let text = line
+            .strip_prefix(&format!("[{}] ", marker))
+            .unwrap_or(&line);
+        let card = format!("{} {}", index + 1, text);
+        column(&mut columns, &status.name()).cards.push(card);
+    }
+    columns
+}
+
+/// A column per tag of the items, that aren't closed. Items with several
+/// tags show up in each of their columns.
+fn by_tag(task_list: &TaskList) -> Vec<Column> {
+    let mut columns: Vec<Column> = vec![];
+    let mut untagged = vec![];
+    for (index, position) in task_list.display_order().into_iter().enumerate() {
+        let task: &Task = &task_list.tasks[position];
+        if task.status.is_closed() {
+            continue;
+        }
+        let card = format!(
+            "{} {}",
+            index + 1,
+            task.without_dates().display_with(&task_list.markers)
+        );
+        match task.tags().as_slice() {
+            [] => untagged.push(card),
+            tags => {
+                for tag in tags {
+                    column(&mut columns, &format!("+{}", tag))
+                        .cards
+                        .push(card.clone());
+                }
+            }
+        }
+    }
+    columns.sort_by(|a, b| a.title.cmp(&b.title));
+    if !untagged.is_empty() {
+        columns.push(Column {
+            title: "(no tag)".to_string(),
+            cards: untagged,
+        });
+    }
+    columns
+}
+
+/// Breaks `text` into lines of at most `width` characters. Lines after the
+/// first are indented by `indent` spaces, words too long for a line are split.
+fn wrap(text: &str, width: usize, indent: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        loop {
+            let line_length = line.chars().count();
+            let space = match line.trim().is_empty() {
+                true => 0,
+                false => 1,
+            };
+            let fits = width.saturating_sub(line_length + space);
+            if word.len() <= fits {
+                if space == 1 {
+                    line.push(' ');
+                }
+                line.extend(word);
+                break;
+            }
+            // only split words, that don't fit on an empty line either
+            if line.trim().is_empty() || word.len() > width.saturating_sub(indent) {
+                if space == 1 {
+                    line.push(' ');
+                }
+                line.extend(word.drain(..fits));
+            }
+            lines.push(line);
+            line = " ".repeat(indent);
+        }
+    }
+    if !line.trim().is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        text,
+        " ".repeat(width.saturating_sub(text.chars().count()))
+    )
+}
+
+/// Draws the columns side by side, so that they fit into `width` characters
+fn render(columns: &[Column], width: usize) -> String {
+    let count = columns.len().max(1);
+    let column_width = (width.saturating_sub(SEPARATOR.chars().count() * (count - 1)) / count)
+        .max(MIN_COLUMN_WIDTH);
+    let join = |cells: Vec<String>| -> String {
+        let line: Vec<String> = cells.iter().map(|cell| pad(cell, column_width)).collect();
+        line.join(SEPARATOR).trim_end().to_string() + "\n"
+    };
+
+    let mut board = join(
+        columns
+            .iter()
+            .map(|column| {
+                let title = format!("{} ({})", column.title, column.cards.len());
+                title.chars().take(column_width).collect()
+            })
+            .collect(),
+    );
+    board += &vec!["─".repeat(column_width); columns.len()].join("─┼─");
+    board += "\n";
+    let cells: Vec<Vec<String>> = columns
+        .iter()
+        .map(|column| {
+            column
+                .cards
+                .iter()
+                .flat_map(|card| {
+                    let indent = card.find(' ').map_or(0, |position| position + 1);
+                    let indent = indent.min(column_width / 2);
+                    wrap(card, column_width, indent)
+                })
+                .collect()
+        })
+        .collect();
+    let rows = cells.iter().map(|cells| cells.len()).max().unwrap_or(0);
+    for row in 0..rows {
+        board += &join(
+            cells
+                .iter()
+                .map(|cells| cells.get(row).cloned().unwrap_or_default())
+                .collect(),
+        );
+    }
+    board
+}
+
+/// The list as a board with a column per status or tag, `width` characters
+/// wide. Items keep the index they have in `todo list`.
+pub(crate) fn board(task_list: &TaskList, columns: Columns, width: usize) -> String {
+    let columns = match columns {
+        Columns::Status => by_status(task_list),
+        Columns::Tag => by_tag(task_list),
+    };
+    render(&columns, width)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wrap_indents_and_splits_long_words() {
+        assert_eq!(
+            vec!["12 Write the", "   release", "   notes"],
+            wrap("12 Write the release notes", 12, 3)
+        );
+        assert_eq!(
+            vec!["1 Read abcde", "  fghijklmn"],
+            wrap("1 Read abcdefghijklmn", 12, 2)
+        );
+    }
+
+    #[test]
+    fn test_board_by_status() {
+        let task_list = "[ ] Fix crash\n[x] Tests\n[~] Write the release notes\n\
+                         [ ] Publish after:1\n[ ] Tag version id:1"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(
+            "in-progress (1) │ open (2)        │ blocked (1)     │ done (1)\n\
+             ────────────────┼─────────────────┼─────────────────┼────────────────\n\
+             1 Write the     │ 2 Fix crash     │ 4 Publish       │ 5 Tests\n\
+             \x20 release notes │ 3 Tag version   │   after:1       │\n\
+             \x20               │   id:1          │                 │\n",
+            board(&task_list, Columns::Status, 70)
+        );
+    }
+
+    #[test]
+    fn test_board_by_tag() {
+        let task_list = "[ ] Invoice +acme\n[x] Call +acme\n[~] Review +acme +billing\n[ ] Docs"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(
+            "+acme (2)                │ +billing (1)             │ (no tag) (1)\n\
+             ─────────────────────────┼──────────────────────────┼─────────────────────────\n\
+             1 [~] Review +acme       │ 1 [~] Review +acme       │ 3 [ ] Docs\n\
+             \x20 +billing               │   +billing               │\n\
+             2 [ ] Invoice +acme      │                          │\n",
+            board(&task_list, Columns::Tag, 80)
+        );
+    }
+}
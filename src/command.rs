@@ -1,74 +1,1234 @@
+use crate::board::Columns;
+use crate::config::Config;
+use crate::diff::Diff;
+use crate::estimate::{accuracy, Estimate, Total};
+use crate::export::ExportFormat;
+use crate::git;
+use crate::ical::ical_status;
+use crate::merge::merge;
+use crate::scan::{comments_in, relative_path, remove_comment, Comment};
+use crate::stats::{OutputFormat, Stats};
+use crate::status::Markers;
+use crate::timelog::{format_duration, Entry, Grouping, TimeLog};
 use crate::Error;
+use crate::Status;
+use crate::Task;
 use crate::TaskList;
+use chrono::{NaiveDate, NaiveDateTime};
+use ignore::WalkBuilder;
 use std::fs;
 use std::fs::File;
-use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
-pub fn create(path: &std::path::Path) -> Result<(), Error> {
-    let file_path = path.join(".todo");
+/// Directory, that holds the named lists next to the default `.todo`
+const LISTS_DIR: &str = ".todo.d";
+
+/// Path of the list called `name` in `dir`, or of the default `.todo`. A
+/// named list is kept in `.todo.d/`, or as `<name>.todo` next to `.todo`.
+/// New lists go to `.todo.d/`.
+pub fn list_path(dir: &Path, name: Option<&str>) -> PathBuf {
+    let name = match name {
+        Some(name) => format!("{}.todo", name),
+        None => return dir.join(".todo"),
+    };
+    let in_lists_dir = dir.join(LISTS_DIR).join(&name);
+    match (in_lists_dir.exists(), dir.join(&name)) {
+        (false, next_to_default) if next_to_default.is_file() => next_to_default,
+        _ => in_lists_dir,
+    }
+}
+
+/// Names of the lists in `dir`, that end in `.todo`
+fn list_names(dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "todo")
+        })
+        .map(|path| path.file_stem().unwrap().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Refuses list names, that would point outside of `.todo.d/`, like `../x`
+pub fn check_list_name(name: &str) -> Result<(), Error> {
+    match name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        true => Err(Error::InvalidListName(name.to_string())),
+        false => Ok(()),
+    }
+}
+
+/// Reads the list at `file_path` with the markers of its project
+fn read_list(file_path: &Path, markers: &Markers) -> Result<TaskList, Error> {
+    let file_content = fs::read_to_string(file_path).map_err(|_| Error::NoList)?;
+    TaskList::parse_with(&file_content, markers)
+}
+
+/// Directory the list at `file_path` belongs to. Named lists in `.todo.d/`
+/// belong to the directory containing it.
+fn list_dir(file_path: &Path) -> &Path {
+    let dir = match file_path.parent() {
+        Some(dir) if dir.ends_with(LISTS_DIR) => dir.parent(),
+        dir => dir,
+    };
+    match dir {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+pub fn create(path: &std::path::Path, name: Option<&str>) -> Result<(), Error> {
+    let file_path = list_path(path, name);
     if file_path.exists() {
         Err(Error::FileExists)
     } else {
-        File::create(path.join(".todo")).expect("File could not be created");
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).expect("Directory could not be created");
+        }
+        File::create(file_path).expect("File could not be created");
         Ok(())
     }
 }
 
-pub fn list(file_path: &std::path::Path, writer: &mut impl std::io::Write) {
-    let file_content = fs::read_to_string(file_path).expect("Couldn't read file contents");
-    let task_list = file_content.parse::<TaskList>().unwrap();
-    write!(writer, "{}", task_list)
-        .unwrap_or_else(|_| panic!("Error parsing file {}", file_path.display()));
+/// Finds all lists below `dir`, skipping everything ignored by git.
+/// The returned paths are relative to `dir`.
+pub fn find_lists(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = WalkBuilder::new(dir)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter(|entry| {
+            let path = entry.path();
+            path.file_name().is_some_and(|name| name == ".todo")
+                || path
+                    .extension()
+                    .is_some_and(|extension| extension == "todo")
+        })
+        .map(|entry| entry.path().strip_prefix(dir).unwrap().to_path_buf())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Lists all lists below `dir`. Either grouped by list or, if `flat` is set,
+/// as one list where every item is prefixed with `path:` of its list.
+pub fn list_recursive(
+    dir: &Path,
+    flat: bool,
+    show_dates: bool,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_lists = find_lists(dir)
+        .into_iter()
+        .map(|path| {
+            let file_path = dir.join(&path);
+            let markers = Config::load(list_dir(&file_path)).markers;
+            Ok((path, read_list(&file_path, &markers)?))
+        })
+        .collect::<Result<Vec<(PathBuf, TaskList)>, Error>>()?;
+
+    if flat {
+        let mut unfinished = vec![];
+        let mut finished = vec![];
+        for (path, task_list) in task_lists.iter() {
+            for (index, position) in task_list.display_order().into_iter().enumerate() {
+                let task = &task_list.tasks[position];
+                let shown_task = match show_dates {
+                    true => task.clone(),
+                    false => task.without_dates(),
+                };
+                let line = format!(
+                    "{}:{} {}",
+                    path.display(),
+                    index + 1,
+                    shown_task.display_with(&task_list.markers)
+                );
+                match task.status.is_closed() {
+                    true => finished.push(line),
+                    false => unfinished.push(line),
+                }
+            }
+        }
+        for line in unfinished {
+            writeln!(writer, "{}", line).unwrap();
+        }
+        writeln!(writer).unwrap();
+        for line in finished {
+            writeln!(writer, "{}", line).unwrap();
+        }
+    } else {
+        for (path, task_list) in task_lists.iter() {
+            writeln!(
+                writer,
+                "{} ({} open, {} done)",
+                path.display(),
+                task_list.unfinished_tasks().len(),
+                task_list.finished_tasks().len()
+            )
+            .unwrap();
+            write!(writer, "{}", task_list.listing(show_dates)).unwrap();
+            writeln!(writer).unwrap();
+        }
+    }
+    Ok(())
+}
+
+/// Prints every list in `dir` with the number of open and finished items
+pub fn lists(dir: &Path, markers: &Markers, writer: &mut impl std::io::Write) -> Result<(), Error> {
+    let mut names: Vec<Option<String>> = list_names(&dir.join(LISTS_DIR))
+        .into_iter()
+        .chain(list_names(dir))
+        .map(Some)
+        .collect();
+    names.sort();
+    names.dedup();
+    names.insert(0, None);
+
+    for name in names {
+        let file_path = list_path(dir, name.as_deref());
+        if file_path.exists() {
+            let task_list = read_list(&file_path, markers)?;
+            writeln!(
+                writer,
+                "{} ({} open, {} done)",
+                name.as_deref().unwrap_or("default"),
+                task_list.unfinished_tasks().len(),
+                task_list.finished_tasks().len()
+            )
+            .unwrap();
+        }
+    }
+    Ok(())
+}
+
+pub fn list(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    show_dates: bool,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    write!(writer, "{}", task_list.listing(show_dates)).unwrap();
+    Ok(())
+}
+
+/// Prints the estimates summed up per status and per tag, after the listing
+pub fn list_estimates(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let by_status = task_list.estimates_by_status();
+    let by_tag: Vec<(String, Total)> = task_list.estimates_by_tag().into_iter().collect();
+    for (title, totals) in [("status", by_status), ("tag", by_tag)] {
+        if totals.is_empty() {
+            continue;
+        }
+        writeln!(writer, "Estimates by {}:", title).unwrap();
+        for (key, total) in totals {
+            writeln!(writer, "{}\t{}", total, key).unwrap();
+        }
+    }
+    Ok(())
+}
+
+/// Prints the open items, that fit into `capacity`, picked by priority and
+/// due date, with their index in `todo list`
+pub fn plan(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    capacity: Estimate,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let display_order = task_list.display_order();
+    let mut planned = Total::default();
+    for position in task_list.plan(capacity) {
+        let task = &task_list.tasks[position];
+        let index = display_order.iter().position(|&p| p == position).unwrap() + 1;
+        writeln!(writer, "{} {}", index, task_list.show(task)).unwrap();
+        planned += task.estimate.unwrap();
+    }
+    let mut available = Total::default();
+    available += capacity;
+    writeln!(writer, "Planned: {} of {}", planned, available).unwrap();
+    Ok(())
 }
 
-pub fn add(file_path: &std::path::Path, text: &str, error_writer: &mut impl std::io::Write) {
-    match OpenOptions::new().append(true).open(file_path) {
-        Ok(mut file) => {
-            file.write_all(b"[ ] ").unwrap();
-            file.write_all(text.as_bytes()).unwrap();
-            file.write_all(b"\n").unwrap();
-            println!("Added: {}", text);
+/// Compares the estimated and the tracked time of the done items, that were
+/// estimated in hours or minutes
+pub fn report_estimates(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let time_log = TimeLog::load(file_path)?;
+    let (mut estimated, mut tracked) = (0u32, chrono::Duration::zero());
+    for task in task_list.tasks.iter().filter(|task| task.status.is_done()) {
+        let (minutes, id) = match (task.estimate, task.id) {
+            (Some(Estimate::Minutes(minutes)), Some(id)) => (minutes, id),
+            _ => continue,
+        };
+        let duration = time_log.tracked(id, now);
+        if duration.is_zero() {
+            continue;
         }
-        Err(_) => {
-            writeln!(error_writer, "Error: There is no list in this directory").unwrap();
-            std::process::exit(1);
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            format_duration(chrono::Duration::minutes(minutes as i64)),
+            format_duration(duration),
+            accuracy(minutes, duration),
+            task.text
+        )
+        .unwrap();
+        estimated = estimated.saturating_add(minutes);
+        tracked += duration;
+    }
+    writeln!(
+        writer,
+        "{}\t{}\t{}\tTotal",
+        format_duration(chrono::Duration::minutes(estimated as i64)),
+        format_duration(tracked),
+        accuracy(estimated, tracked)
+    )
+    .unwrap();
+    Ok(())
+}
+
+/// Appends a new task, created `on` the given date, to the list. Unless
+/// `duplicate_threshold` is `None`, the task is refused if a similar
+/// unfinished task is already on the list.
+pub fn add(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    text: &str,
+    on: NaiveDate,
+    duplicate_threshold: Option<f64>,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    add_in(&mut task_list, text, on, duplicate_threshold, writer)?;
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+/// Prints the open items as `index<TAB>text` for the shell completion. Prints
+/// nothing if there is no list, so completing never shows an error.
+pub fn complete_items(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    writer: &mut impl std::io::Write,
+) {
+    let task_list = match read_list(file_path, markers) {
+        Ok(task_list) => task_list,
+        Err(_) => return,
+    };
+    for (index, position) in task_list.display_order().into_iter().enumerate() {
+        let task = &task_list.tasks[position];
+        if !task.status.is_closed() {
+            writeln!(writer, "{}\t{}", index + 1, task.text).unwrap();
         }
     }
 }
 
-pub fn check(file_path: &std::path::Path, item_index: usize, writer: &mut impl std::io::Write) {
-    let file_content = fs::read_to_string(file_path).expect("Couldn't read file contents"); // TODO: make these two lines their own function
-    let mut task_list = file_content.parse::<TaskList>().unwrap();
-    let checked_task = task_list.check(item_index).unwrap();
+/// Checks an item and records the `commit`, that finished it, if given.
+/// Checking the item, whose time is being tracked, stops the tracking.
+pub fn check(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    now: NaiveDateTime,
+    commit: Option<&str>,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    if let Some(commit) = commit {
+        let position = task_list.position(item_index)?;
+        task_list.tasks[position].commit = Some(commit.to_string());
+    }
+    let checked_task = check_in(&mut task_list, item_index, now.date(), writer)?;
     fs::write(file_path, task_list.to_string_unordered()).unwrap();
-    writeln!(writer, "{}", checked_task).unwrap();
+    stop_tracking(file_path, &task_list, &checked_task, now, writer)?;
     // TODO: maybe use mark or toggle instead of check
+    Ok(())
+}
+
+// The changes below are shared by the commands and `todo shell`. They change
+// the list in memory and print what they did; writing the list is up to the
+// caller.
+
+/// Adds a task created `on` the given date, see `add`
+pub(crate) fn add_in(
+    task_list: &mut TaskList,
+    text: &str,
+    on: NaiveDate,
+    duplicate_threshold: Option<f64>,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    if let Some(threshold) = duplicate_threshold {
+        if let Some(duplicate) = task_list.find_duplicate(text, threshold) {
+            return Err(Error::Duplicate(Box::new(duplicate)));
+        }
+    }
+    task_list.add(Task {
+        text: text.to_string(),
+        created: Some(on),
+        ..Default::default()
+    });
+    writeln!(writer, "Added: {}", text).unwrap();
+    Ok(())
+}
+
+/// Checks an item and prints the next occurrence of a recurring one.
+/// Returns the checked task, so the caller can stop its time tracking.
+pub(crate) fn check_in(
+    task_list: &mut TaskList,
+    item_index: usize,
+    on: NaiveDate,
+    writer: &mut impl std::io::Write,
+) -> Result<Task, Error> {
+    let task_count = task_list.tasks.len();
+    let checked_task = task_list.check(item_index, on)?;
+    writeln!(writer, "{}", task_list.show(&checked_task)).unwrap();
+    if task_list.tasks.len() > task_count {
+        let next_task = task_list.tasks.last().unwrap();
+        writeln!(writer, "Next: {}", task_list.show(next_task)).unwrap();
+    }
+    Ok(checked_task)
+}
+
+pub(crate) fn uncheck_in(
+    task_list: &mut TaskList,
+    item_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let unchecked_task = task_list.uncheck(item_index)?;
+    writeln!(writer, "{}", task_list.show(&unchecked_task)).unwrap();
+    Ok(())
+}
+
+pub(crate) fn edit_in(
+    task_list: &mut TaskList,
+    item_index: usize,
+    text: &str,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let edited_task = task_list.edit(item_index, text)?;
+    writeln!(writer, "Edited: {}", task_list.show(&edited_task)).unwrap();
+    Ok(())
+}
+
+pub(crate) fn remove_in(
+    task_list: &mut TaskList,
+    item_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let removed_task = task_list.remove(item_index)?;
+    writeln!(writer, "Removed: {}", task_list.show(&removed_task)).unwrap();
+    Ok(())
+}
+
+pub(crate) fn move_in(
+    task_list: &mut TaskList,
+    item_index: usize,
+    new_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let moved_task = task_list.move_task(item_index, new_index)?;
+    writeln!(writer, "Moved: {}", task_list.show(&moved_task)).unwrap();
+    Ok(())
+}
+
+/// Merges the duplicates and returns how many were removed
+pub(crate) fn dedupe_in(
+    task_list: &mut TaskList,
+    threshold: f64,
+    writer: &mut impl std::io::Write,
+) -> usize {
+    let removed_tasks = task_list.dedupe(threshold);
+    for removed_task in removed_tasks.iter() {
+        writeln!(writer, "Merged: {}", task_list.show(removed_task)).unwrap();
+    }
+    writeln!(writer, "Removed {} duplicate(s)", removed_tasks.len()).unwrap();
+    removed_tasks.len()
+}
+
+/// Stops the time tracking, if `task` is the tracked item. Used by every
+/// way of checking an item: the commands, `todo shell` and `todo ui`.
+pub(crate) fn stop_tracking(
+    file_path: &std::path::Path,
+    task_list: &TaskList,
+    task: &Task,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut time_log = TimeLog::load(file_path)?;
+    if task.id.is_some() && time_log.active().map(|entry| entry.id) == task.id {
+        let stopped = time_log.stop(now).unwrap();
+        time_log.save(file_path);
+        write_stopped(task_list, &stopped, writer);
+    }
+    Ok(())
+}
+
+pub fn remove(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    remove_in(&mut task_list, item_index, writer)?;
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+pub fn uncheck(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    uncheck_in(&mut task_list, item_index, writer)?;
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+/// Marks an item as blocked, waiting or cancelled. Cancelling the item, whose
+/// time is being tracked, stops the tracking.
+pub fn set_status(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    status: Status,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    let changed_task = task_list.set_status(item_index, status, now.date())?;
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    writeln!(writer, "{}", task_list.show(&changed_task)).unwrap();
+    if changed_task.status.is_closed() {
+        stop_tracking(file_path, &task_list, &changed_task, now, writer)?;
+    }
+    Ok(())
+}
+
+pub fn edit(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    text: &str,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    edit_in(&mut task_list, item_index, text, writer)?;
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+/// Moves an item to the column of `status` on the board. Moving it to done
+/// checks it, with everything `check` does.
+pub fn move_status(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    status: Status,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    match status {
+        Status::Finished => check(file_path, markers, item_index, now, None, writer),
+        status => set_status(file_path, markers, item_index, status, now, writer),
+    }
+}
+
+/// Prints the list as a board with a column per status or tag
+pub fn board(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    columns: Columns,
+    width: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    write!(
+        writer,
+        "{}",
+        crate::board::board(&task_list, columns, width)
+    )
+    .unwrap();
+    Ok(())
+}
+
+/// Installs a `commit-msg` hook in the repository containing `dir`, that runs
+/// `executable` to check the items referenced in the commit message
+pub fn install_hook(
+    dir: &Path,
+    executable: &Path,
+    list_name: Option<&str>,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let hook_path = git::hooks_dir(dir)?.join("commit-msg");
+    let hook = git::commit_msg_hook(executable, list_name);
+    if let Ok(installed_hook) = fs::read_to_string(&hook_path) {
+        if !installed_hook.contains("# Installed by todo git-hook install") {
+            return Err(Error::HookExists);
+        }
+    }
+    fs::create_dir_all(hook_path.parent().unwrap()).expect("Directory could not be created");
+    fs::write(&hook_path, hook).expect("File could not be created");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook_path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    writeln!(writer, "Installed: {}", hook_path.display()).unwrap();
+    Ok(())
+}
+
+/// Checks the items referenced by the `Todo:` trailers of the commit message
+/// in `message_path`. References by index are replaced with the `id:` of the
+/// item in the message, so they stay valid when the list changes. Items, that
+/// can't be checked, are reported, but don't stop the commit.
+pub fn commit_msg(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    message_path: &Path,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let message = fs::read_to_string(message_path).expect("Couldn't read the commit message");
+    let references = git::references(&message);
+    if references.is_empty() {
+        return Ok(());
+    }
+    let mut task_list = read_list(file_path, markers)?;
+    let mut ids = vec![];
+    for reference in references {
+        let id = match reference {
+            git::Reference::Index(index) => task_list.identify(index),
+            git::Reference::Id(id) => task_list
+                .index_of(id)
+                .map(|_| id)
+                .ok_or(Error::IndexOutOfBounds),
+        };
+        match id {
+            Ok(id) => ids.push(id),
+            Err(error) => writeln!(writer, "Warning: {}: {}", reference, error).unwrap(),
+        }
+    }
+    let message = git::rewrite_references(&message, |index| {
+        let position = task_list.position(index).ok()?;
+        task_list.tasks[position].id
+    });
+    let mut checked_tasks = vec![];
+    for id in ids {
+        let index = task_list.index_of(id).ok_or(Error::IndexOutOfBounds)?;
+        let position = task_list.position(index)?;
+        if task_list.tasks[position].status.is_done() {
+            continue;
+        }
+        match task_list.check(index, now.date()) {
+            Ok(checked_task) => {
+                writeln!(writer, "{}", task_list.show(&checked_task)).unwrap();
+                checked_tasks.push(checked_task);
+            }
+            Err(error) => writeln!(writer, "Warning: id:{}: {}", id, error).unwrap(),
+        }
+    }
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    fs::write(message_path, message).unwrap();
+    for checked_task in checked_tasks {
+        stop_tracking(file_path, &task_list, &checked_task, now, writer)?;
+    }
+    Ok(())
+}
+
+/// Merge driver for git: merges the lists `base`, `ours` and `theirs` and
+/// writes the result to `ours`
+pub fn merge_driver(
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+    markers: &Markers,
+) -> Result<(), Error> {
+    let read = |path: &Path| fs::read_to_string(path).map_err(|_| Error::NoList);
+    let (merged, conflicts) = merge(&read(base)?, &read(ours)?, &read(theirs)?, markers)?;
+    fs::write(ours, merged).unwrap();
+    match conflicts {
+        0 => Ok(()),
+        count => Err(Error::MergeConflicts(count)),
+    }
+}
+
+/// Registers `executable` as the merge driver of the lists in the repository
+/// containing `dir`, in its local config and `.gitattributes`
+pub fn git_setup(
+    dir: &Path,
+    executable: &Path,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let driver = format!(
+        "{} merge-driver %O %A %B",
+        shell_words::quote(&executable.to_string_lossy())
+    );
+    git::set_config(dir, "merge.todo.name", "todo list merge")?;
+    git::set_config(dir, "merge.todo.driver", &driver)?;
+
+    let attributes_path = dir.join(".gitattributes");
+    let mut attributes = fs::read_to_string(&attributes_path).unwrap_or_default();
+    for line in git::MERGE_ATTRIBUTES {
+        if !attributes.lines().any(|existing| existing.trim() == line) {
+            if !attributes.is_empty() && !attributes.ends_with('\n') {
+                attributes += "\n";
+            }
+            attributes += &format!("{}\n", line);
+        }
+    }
+    fs::write(&attributes_path, attributes).unwrap();
+    writeln!(writer, "Registered the merge driver for .todo files").unwrap();
+    Ok(())
+}
+
+/// Prints the changes to the list at `list_path`, relative to the repository
+/// in `dir`, between two git revisions: `<old>..<new>`, or `<old>` for the
+/// changes since then. Without a range, the working tree is compared to `HEAD`.
+pub fn diff(
+    dir: &Path,
+    list_path: &Path,
+    range: Option<&str>,
+    markers: &Markers,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    fn or_head(rev: &str) -> &str {
+        match rev {
+            "" => "HEAD",
+            rev => rev,
+        }
+    }
+    let (old_rev, new_rev) = match range.map(|range| range.split_once("..")) {
+        None => ("HEAD", None),
+        Some(None) => (range.unwrap(), None),
+        Some(Some((old, new))) => (or_head(old), Some(or_head(new))),
+    };
+    let old = git::file_at(dir, old_rev, list_path)?.unwrap_or_default();
+    let new = match new_rev {
+        Some(new_rev) => git::file_at(dir, new_rev, list_path)?.unwrap_or_default(),
+        None => fs::read_to_string(dir.join(list_path)).map_err(|_| Error::NoList)?,
+    };
+    let diff = Diff::new(&old, &new, markers)?;
+    match diff.is_empty() {
+        true => writeln!(writer, "No changes").unwrap(),
+        false => write!(writer, "{}", diff).unwrap(),
+    }
+    Ok(())
+}
+
+/// Prints an item and the items it comes `after:`, as a tree
+pub fn deps(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let position = task_list.position(item_index)?;
+    write!(writer, "{}", task_list.dependency_tree(position)).unwrap();
+    Ok(())
+}
+
+/// Opens the notes of an item in `editor`, e.g. `$EDITOR`, and saves them
+/// once the editor is closed
+pub fn note(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    editor: &str,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    let notes = &task_list.tasks[task_list.position(item_index)?].notes;
+    // created with a random name and removed when dropped
+    let mut notes_file = tempfile::Builder::new()
+        .prefix("todo-note-")
+        .suffix(".md")
+        .tempfile()
+        .map_err(|_| Error::EditorFailed)?;
+    for note in notes {
+        writeln!(notes_file, "{}", note).unwrap();
+    }
+    notes_file.flush().unwrap();
+    let notes_path = notes_file.path();
+
+    let words = shell_words::split(editor).map_err(|_| Error::EditorFailed)?;
+    let (program, arguments) = words.split_first().ok_or(Error::EditorFailed)?;
+    let status = std::process::Command::new(program)
+        .args(arguments)
+        .arg(notes_path)
+        .status();
+    let edited_notes = fs::read_to_string(notes_path).unwrap_or_default();
+    drop(notes_file);
+    if !status.is_ok_and(|status| status.success()) {
+        return Err(Error::EditorFailed);
+    }
+
+    let mut notes: Vec<String> = edited_notes.lines().map(str::to_string).collect();
+    while notes.last().is_some_and(|note| note.trim().is_empty()) {
+        notes.pop();
+    }
+    let annotated_task = task_list.annotate(item_index, notes)?;
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    writeln!(writer, "Noted: {}", task_list.show(&annotated_task)).unwrap();
+    Ok(())
+}
+
+/// Prints an item with its dates, followed by its notes
+pub fn show(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let task = &task_list.tasks[task_list.position(item_index)?];
+    writeln!(writer, "{}", task.display_with(markers)).unwrap();
+    if !task.notes.is_empty() {
+        writeln!(writer).unwrap();
+        for note in task.notes.iter() {
+            writeln!(writer, "{}", note).unwrap();
+        }
+    }
+    Ok(())
 }
 
-pub fn remove(file_path: &std::path::Path, item_index: usize, writer: &mut impl std::io::Write) {
-    let file_content = fs::read_to_string(file_path).expect("Couldn't read file contents"); // TODO: make these two lines their own function
-    let mut task_list = file_content.parse::<TaskList>().unwrap();
-    let removed_task = task_list.remove(item_index).unwrap();
+/// Moves an item to `new_index`, `top` and `bottom` are `1` and `usize::MAX`
+pub fn move_task(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    new_index: usize,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    move_in(&mut task_list, item_index, new_index, writer)?;
     fs::write(file_path, task_list.to_string_unordered()).unwrap();
-    writeln!(writer, "Removed: {}", removed_task).unwrap();
-    // TODO: make a warning for trying to remove an Item with the wrong index
+    Ok(())
+}
+
+/// Offers every new `TODO:` comment below `paths` as a task. Comments, that
+/// are already on the list, are skipped. Every answer is read from `reader`,
+/// unless `yes` is set.
+pub fn scan(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    paths: &[PathBuf],
+    on: NaiveDate,
+    yes: bool,
+    reader: &mut impl std::io::BufRead,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    // sources are stored relative to the list, comments are found relative
+    // to the current directory
+    let dir = list_dir(file_path);
+    let imported: Vec<Task> = task_list
+        .tasks
+        .iter()
+        .map(|task| {
+            let mut task = task.clone();
+            if let Some(source) = task.source.as_mut() {
+                source.path = relative_path(dir, &dir.join(&source.path));
+            }
+            task
+        })
+        .collect();
+    let mut added = 0;
+    for comment in crate::scan::scan(paths) {
+        let comment = Comment {
+            path: relative_path(dir, &comment.path),
+            ..comment
+        };
+        let is_imported = imported.iter().any(|task| comment.is_imported_as(task));
+        if is_imported {
+            continue;
+        }
+        let add = yes || {
+            write!(
+                writer,
+                "{}:{} {} Add? [y/N] ",
+                comment.path.display(),
+                comment.line,
+                comment.text
+            )
+            .unwrap();
+            writer.flush().unwrap();
+            let mut answer = String::new();
+            reader.read_line(&mut answer).unwrap();
+            answer.trim().eq_ignore_ascii_case("y")
+        };
+        if add {
+            let task = Task {
+                created: Some(on),
+                ..comment.to_task()
+            };
+            writeln!(writer, "Added: {}", task_list.show(&task)).unwrap();
+            task_list.add(task);
+            added += 1;
+        }
+    }
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    writeln!(writer, "Added {} item(s)", added).unwrap();
+    Ok(())
+}
+
+/// The comment `task` was imported from, by a list in `dir`. Comments, that
+/// moved, are found by their text. If the text appears several times, the
+/// closest one wins.
+fn find_source_comment(dir: &Path, task: &Task) -> Option<Comment> {
+    let source = task.source.as_ref()?;
+    comments_in(&dir.join(&source.path))?
+        .into_iter()
+        .filter(|comment| comment.text == task.text)
+        .min_by_key(|comment| comment.line.abs_diff(source.line))
+}
+
+/// Updates items imported by `scan` to the state of their comments: items,
+/// whose comment is gone, are checked and moved comments update the line of
+/// the item. With `remove_comments`, comments of checked items are removed.
+pub fn sync_code(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    remove_comments: bool,
+    on: NaiveDate,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    let dir = list_dir(file_path);
+
+    // comments are removed first, so that lines shifted by the removal are
+    // picked up below
+    if remove_comments {
+        for task in task_list.tasks.iter().filter(|task| task.status.is_done()) {
+            if let Some(comment) = find_source_comment(dir, task) {
+                if remove_comment(&comment.path, comment.line) {
+                    writeln!(
+                        writer,
+                        "Removed comment: {}:{} {}",
+                        task.source.as_ref().unwrap().path.display(),
+                        comment.line,
+                        comment.text
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    for task in task_list.tasks.iter_mut() {
+        if task.status.is_closed() || task.source.is_none() {
+            continue;
+        }
+        match find_source_comment(dir, task) {
+            Some(comment) => {
+                let source = task.source.as_mut().unwrap();
+                if source.line != comment.line {
+                    source.line = comment.line;
+                    writeln!(
+                        writer,
+                        "Moved: {}",
+                        task.without_dates().display_with(markers)
+                    )
+                    .unwrap();
+                }
+            }
+            None => {
+                task.check(on);
+                writeln!(
+                    writer,
+                    "Finished: {}",
+                    task.without_dates().display_with(markers)
+                )
+                .unwrap();
+            }
+        }
+    }
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+pub fn stats(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    since: Option<chrono::NaiveDate>,
+    format: OutputFormat,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let stats = Stats::new(&task_list.tasks, since);
+    match format {
+        OutputFormat::Text => write!(writer, "{}", stats).unwrap(),
+        OutputFormat::Json => writeln!(writer, "{:#}", stats.to_json()).unwrap(),
+    }
+    Ok(())
+}
+
+/// Writes the list in `format` to `output`, using `title` as heading
+pub fn export(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    format: ExportFormat,
+    title: &str,
+    output: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    write!(
+        output,
+        "{}",
+        crate::export::export(&task_list, format, title)
+    )
+    .unwrap();
+    Ok(())
+}
+
+/// Merges the `VTODO`s of an iCalendar file into the list. Tasks with the same
+/// text and creation date are updated, all others are added.
+pub fn import(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    ical_path: &std::path::Path,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    let ical = fs::read_to_string(ical_path).map_err(|_| Error::InvalidCalendar)?;
+    let imported_tasks = crate::ical::from_ical(&ical).map_err(|_| Error::InvalidCalendar)?;
+    for imported_task in imported_tasks {
+        let existing_task = task_list
+            .tasks
+            .iter_mut()
+            .find(|task| task.text == imported_task.text && task.created == imported_task.created);
+        let task = match existing_task {
+            Some(task) => task,
+            None => {
+                writeln!(writer, "Added: {}", task_list.show(&imported_task)).unwrap();
+                task_list.add(imported_task);
+                continue;
+            }
+        };
+        // statuses, that iCalendar can't represent, are exported as one it
+        // knows and stay as they are
+        let status = match ical_status(&imported_task.status) == ical_status(&task.status) {
+            true => task.status.clone(),
+            false => imported_task.status.clone(),
+        };
+        // only compare what iCalendar carries
+        let imported = (
+            status,
+            imported_task.completed,
+            imported_task.due,
+            imported_task.priority,
+        );
+        if imported == (task.status.clone(), task.completed, task.due, task.priority) {
+            continue;
+        }
+        (task.status, task.completed, task.due, task.priority) = imported;
+        writeln!(
+            writer,
+            "Updated: {}",
+            task.without_dates().display_with(markers)
+        )
+        .unwrap();
+    }
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+pub fn dedupe(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    threshold: f64,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    dedupe_in(&mut task_list, threshold, writer);
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    Ok(())
+}
+
+/// Moves an item from the list at `file_path` to the end of the list at `target_path`
+pub fn move_to_list(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    target_path: &std::path::Path,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    let mut target_list = read_list(target_path, markers)?;
+    if fs::canonicalize(file_path).ok() == fs::canonicalize(target_path).ok() {
+        return Err(Error::SameList);
+    }
+    let moved_task = task_list.remove(item_index)?;
+    target_list.add(moved_task.clone());
+    fs::write(target_path, target_list.to_string_unordered()).unwrap();
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    writeln!(writer, "Moved: {}", task_list.show(&moved_task)).unwrap();
+    Ok(())
 }
 
 pub fn destroy(file_path: &std::path::Path, writer: &mut impl std::io::Write) {
     std::fs::remove_file(file_path).unwrap();
-    writeln!(writer, "Deleted: .todo").unwrap();
+    writeln!(
+        writer,
+        "Deleted: {}",
+        file_path.file_name().unwrap().to_string_lossy()
+    )
+    .unwrap();
+}
+
+/// Text of the tracked task, or its id if it was removed from the list
+fn tracked_text(task_list: &TaskList, id: u32) -> String {
+    match task_list.tasks.iter().find(|task| task.id == Some(id)) {
+        Some(task) => task.text.clone(),
+        None => format!("id:{}", id),
+    }
+}
+
+fn write_stopped(task_list: &TaskList, entry: &Entry, writer: &mut impl std::io::Write) {
+    let duration = entry.end.unwrap() - entry.start;
+    writeln!(
+        writer,
+        "Stopped: {} ({})",
+        tracked_text(task_list, entry.id),
+        format_duration(duration)
+    )
+    .unwrap();
+}
+
+/// Marks an item as in progress and starts tracking the time spent on it.
+/// The item gets an `id:`, if it has none yet. Only one item is tracked at a
+/// time, so the item tracked so far is stopped.
+pub fn start(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    item_index: usize,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let mut task_list = read_list(file_path, markers)?;
+    let mut time_log = TimeLog::load(file_path)?;
+    let id = task_list.identify(item_index)?;
+    task_list.set_status(item_index, Status::InProgress, now.date())?;
+    if let Some(stopped) = time_log.start(id, now) {
+        write_stopped(&task_list, &stopped, writer);
+    }
+    fs::write(file_path, task_list.to_string_unordered()).unwrap();
+    time_log.save(file_path);
+    writeln!(writer, "Started: {}", tracked_text(&task_list, id)).unwrap();
+    Ok(())
+}
+
+pub fn stop(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let mut time_log = TimeLog::load(file_path)?;
+    let stopped = time_log.stop(now).ok_or(Error::NotTracking)?;
+    time_log.save(file_path);
+    write_stopped(&task_list, &stopped, writer);
+    Ok(())
+}
+
+/// Prints every tracked interval, the running one up to `now`
+pub fn log(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let time_log = TimeLog::load(file_path)?;
+    for entry in time_log.entries.iter() {
+        let end = match entry.end {
+            Some(end) if end.date() == entry.start.date() => end.format("%H:%M").to_string(),
+            Some(end) => end.format("%Y-%m-%d %H:%M").to_string(),
+            None => "now".to_string(),
+        };
+        writeln!(
+            writer,
+            "{} - {}\t{}\t{}",
+            entry.start.format("%Y-%m-%d %H:%M"),
+            end,
+            format_duration(entry.duration(now)),
+            tracked_text(&task_list, entry.id)
+        )
+        .unwrap();
+    }
+    Ok(())
+}
+
+/// Prints the tracked time per day, item or tag and the total
+pub fn report_time(
+    file_path: &std::path::Path,
+    markers: &Markers,
+    grouping: Grouping,
+    now: NaiveDateTime,
+    writer: &mut impl std::io::Write,
+) -> Result<(), Error> {
+    let task_list = read_list(file_path, markers)?;
+    let time_log = TimeLog::load(file_path)?;
+    let total = time_log
+        .entries
+        .iter()
+        .map(|entry| entry.duration(now))
+        .sum();
+    for (key, duration) in time_log.report(&task_list.tasks, grouping, now) {
+        writeln!(writer, "{}\t{}", format_duration(duration), key).unwrap();
+    }
+    writeln!(writer, "{}\tTotal", format_duration(total)).unwrap();
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::status::CustomStatus;
     use assert_fs::fixture::TempDir;
     use assert_fs::fixture::{FileTouch, NamedTempFile};
     use std::fs::{self, read_to_string, File};
     use std::io::Write;
     use std::path::Path;
 
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, 10, 14).unwrap()
+    }
+
+    fn markers() -> Markers {
+        Markers::default()
+    }
+
     #[test]
     fn test_add_appends_text_to_file() {
         let temp_file = NamedTempFile::new(".todo").unwrap();
@@ -76,23 +1236,44 @@ mod test {
         temp_file.touch().unwrap();
         {
             let mut f = File::options().write(true).open(temp_file.path()).unwrap();
-            f.write(b"[x] Old todo entry\n").unwrap();
+            f.write_all(b"[x] Old todo entry\n").unwrap();
         }
 
-        add(temp_file.path(), &text, &mut std::io::stderr());
+        add(
+            temp_file.path(),
+            &markers(),
+            &text,
+            day(),
+            Some(0.9),
+            &mut std::io::stdout(),
+        )
+        .unwrap();
 
         assert_eq!(
             read_to_string(temp_file.path()).unwrap(),
-            "[x] Old todo entry\n[ ] New todo entry\n".to_string()
+            "[x] Old todo entry\n[ ] 2022-10-14 New todo entry\n".to_string()
         );
         temp_file.close().unwrap();
     }
 
+    #[test]
+    fn test_complete_items_lists_open_items() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[x] Done\n[ ] Task 1\n[ ] Task 2\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        complete_items(&file_path, &markers(), &mut writer);
+        complete_items(&temp_dir.path().join("missing"), &markers(), &mut writer);
+
+        assert_eq!("1\tTask 1\n2\tTask 2\n", String::from_utf8(writer).unwrap());
+    }
+
     #[test]
     fn test_create_function_creates_a_new_file() {
         let temp_dir = TempDir::new().unwrap();
 
-        create(temp_dir.path()).unwrap();
+        create(temp_dir.path(), None).unwrap();
 
         assert!(temp_dir.path().join(Path::new(".todo")).exists());
 
@@ -100,36 +1281,973 @@ mod test {
     }
 
     #[test]
-    fn test_create_function_doesnt_overwrite_existing_file() {
+    fn test_create_named_list() {
         let temp_dir = TempDir::new().unwrap();
-        let file_path = temp_dir.path().join(".todo");
-        {
-            let mut file = File::create(file_path.clone()).unwrap();
-            file.write(b"foo").unwrap();
-        }
 
-        let result = create(temp_dir.path());
+        create(temp_dir.path(), Some("bugs")).unwrap();
 
-        assert!(result.is_err());
+        assert!(temp_dir.path().join(".todo.d/bugs.todo").exists());
+        assert!(!temp_dir.path().join(".todo").exists());
+        assert_eq!(
+            Err(Error::FileExists),
+            create(temp_dir.path(), Some("bugs"))
+        );
+    }
 
-        // file contents should not be altered
-        let file_content = fs::read_to_string(file_path).unwrap();
-        assert_eq!(file_content, "foo");
+    fn create_tree() -> TempDir {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("crates/core")).unwrap();
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::write(root.join(".gitignore"), "target\n").unwrap();
+        fs::write(root.join(".todo"), "[ ] Release\n").unwrap();
+        fs::write(root.join("crates/core/.todo"), "[x] Parse\n[ ] Format\n").unwrap();
+        fs::write(root.join("target/debug/.todo"), "[ ] Ignored\n").unwrap();
+        create(root, Some("bugs")).unwrap();
+        temp_dir
     }
 
     #[test]
-    fn test_add_writes_to_file() {
-        let temp_file = NamedTempFile::new(".todo").unwrap();
-        let text = "New todo entry".to_string();
-        temp_file.touch().unwrap();
+    fn test_find_lists_respects_gitignore() {
+        let temp_dir = create_tree();
+
+        assert_eq!(
+            vec![
+                Path::new(".todo"),
+                Path::new(".todo.d/bugs.todo"),
+                Path::new("crates/core/.todo")
+            ],
+            find_lists(temp_dir.path())
+        );
+    }
+
+    #[test]
+    fn test_list_recursive_grouped() {
+        let temp_dir = create_tree();
+        let mut writer = Vec::<u8>::new();
 
-        add(temp_file.path(), &text, &mut std::io::stderr());
+        list_recursive(temp_dir.path(), false, false, &mut writer).unwrap();
 
         assert_eq!(
-            read_to_string(temp_file.path()).unwrap(),
-            "[ ] New todo entry\n".to_string()
+            ".todo (1 open, 0 done)\n1 [ ] Release\n\n\n\
+             .todo.d/bugs.todo (0 open, 0 done)\n\n\n\
+             crates/core/.todo (1 open, 1 done)\n1 [ ] Format\n\n2 [x] Parse\n\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_list_recursive_flat() {
+        let temp_dir = create_tree();
+        let mut writer = Vec::<u8>::new();
+
+        list_recursive(temp_dir.path(), true, false, &mut writer).unwrap();
+
+        assert_eq!(
+            ".todo:1 [ ] Release\ncrates/core/.todo:1 [ ] Format\n\ncrates/core/.todo:2 [x] Parse\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_lists_shows_counts() {
+        let temp_dir = TempDir::new().unwrap();
+        create(temp_dir.path(), Some("ideas")).unwrap();
+        create(temp_dir.path(), Some("bugs")).unwrap();
+        std::fs::write(temp_dir.path().join(".todo"), "[ ] Task\n[x] Done\n").unwrap();
+        std::fs::write(list_path(temp_dir.path(), Some("bugs")), "[ ] Crash\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        lists(temp_dir.path(), &markers(), &mut writer).unwrap();
+
+        assert_eq!(
+            "default (1 open, 1 done)\nbugs (1 open, 0 done)\nideas (0 open, 0 done)\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_named_list_next_to_default() {
+        let temp_dir = TempDir::new().unwrap();
+        create(temp_dir.path(), Some("ideas")).unwrap();
+        std::fs::write(temp_dir.path().join("bugs.todo"), "[ ] Crash\n").unwrap();
+        std::fs::write(temp_dir.path().join("ideas.todo"), "[ ] Unused\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        lists(temp_dir.path(), &markers(), &mut writer).unwrap();
+
+        assert_eq!(
+            temp_dir.path().join("bugs.todo"),
+            list_path(temp_dir.path(), Some("bugs"))
+        );
+        assert_eq!(
+            temp_dir.path().join(".todo.d").join("ideas.todo"),
+            list_path(temp_dir.path(), Some("ideas"))
+        );
+        assert_eq!(
+            temp_dir.path().join(".todo.d").join("new.todo"),
+            list_path(temp_dir.path(), Some("new"))
+        );
+        assert_eq!(
+            "bugs (1 open, 0 done)\nideas (0 open, 0 done)\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_move_to_list() {
+        let temp_dir = TempDir::new().unwrap();
+        create(temp_dir.path(), None).unwrap();
+        create(temp_dir.path(), Some("ideas")).unwrap();
+        let file_path = list_path(temp_dir.path(), None);
+        let target_path = list_path(temp_dir.path(), Some("ideas"));
+        std::fs::write(&file_path, "[ ] Task\n[ ] Rewrite in Rust\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        move_to_list(&file_path, &markers(), 2, &target_path, &mut writer).unwrap();
+
+        assert_eq!("[ ] Task\n", read_to_string(&file_path).unwrap());
+        assert_eq!(
+            "[ ] Rewrite in Rust\n",
+            read_to_string(&target_path).unwrap()
+        );
+        assert_eq!(
+            Err(Error::NoList),
+            move_to_list(
+                &file_path,
+                &markers(),
+                1,
+                &list_path(temp_dir.path(), Some("nope")),
+                &mut writer
+            )
+        );
+        assert_eq!("[ ] Task\n", read_to_string(&file_path).unwrap());
+        assert_eq!(
+            Err(Error::SameList),
+            move_to_list(&target_path, &markers(), 1, &target_path, &mut writer)
+        );
+        assert_eq!(
+            "[ ] Rewrite in Rust\n",
+            read_to_string(&target_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_list_names_stay_inside_the_lists_dir() {
+        assert_eq!(Ok(()), check_list_name("bugs"));
+        for name in ["", "../x", "a/b", "a\\b", "..", ".hidden"] {
+            assert_eq!(
+                Err(Error::InvalidListName(name.to_string())),
+                check_list_name(name)
+            );
+        }
+    }
+
+    #[test]
+    fn test_create_function_doesnt_overwrite_existing_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        {
+            let mut file = File::create(file_path.clone()).unwrap();
+            file.write_all(b"foo").unwrap();
+        }
+
+        let result = create(temp_dir.path(), None);
+
+        assert!(result.is_err());
+
+        // file contents should not be altered
+        let file_content = fs::read_to_string(file_path).unwrap();
+        assert_eq!(file_content, "foo");
+    }
+
+    #[test]
+    fn test_add_writes_to_file() {
+        let temp_file = NamedTempFile::new(".todo").unwrap();
+        let text = "New todo entry".to_string();
+        temp_file.touch().unwrap();
+
+        add(
+            temp_file.path(),
+            &markers(),
+            &text,
+            day(),
+            Some(0.9),
+            &mut std::io::stdout(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_to_string(temp_file.path()).unwrap(),
+            "[ ] 2022-10-14 New todo entry\n".to_string()
+        );
+        temp_file.close().unwrap();
+    }
+
+    #[test]
+    fn test_add_refuses_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Refactor code\n").unwrap();
+
+        let result = add(
+            &file_path,
+            &markers(),
+            "refactor  Code",
+            day(),
+            Some(0.9),
+            &mut Vec::new(),
+        );
+
+        assert_eq!(
+            Err(Error::Duplicate(Box::new(
+                "[ ] Refactor code".parse().unwrap()
+            ))),
+            result
+        );
+        assert_eq!("[ ] Refactor code\n", read_to_string(&file_path).unwrap());
+
+        add(
+            &file_path,
+            &markers(),
+            "refactor  Code",
+            day(),
+            None,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            "[ ] Refactor code\n[ ] 2022-10-14 refactor  Code\n",
+            read_to_string(&file_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_add_without_list_fails() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let result = add(
+            &temp_dir.path().join(".todo"),
+            &markers(),
+            "Text",
+            day(),
+            None,
+            &mut Vec::new(),
+        );
+
+        assert_eq!(Err(Error::NoList), result);
+    }
+
+    #[test]
+    fn test_move_task_rewrites_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Task 1\n[ ] Task 2\n[ ] Task 3\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        move_task(&file_path, &markers(), 3, 1, &mut writer).unwrap();
+
+        assert_eq!(
+            "[ ] Task 3\n[ ] Task 1\n[ ] Task 2\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!("Moved: [ ] Task 3\n", String::from_utf8(writer).unwrap());
+        assert_eq!(
+            Err(Error::IndexOutOfBounds),
+            move_task(&file_path, &markers(), 4, 1, &mut Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_remove_reports_missing_list_and_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n[ ] Task 2\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        remove(&file_path, &markers(), 1, &mut writer).unwrap();
+
+        assert_eq!(
+            Err(Error::IndexOutOfBounds),
+            remove(&file_path, &markers(), 2, &mut writer)
+        );
+        assert_eq!(
+            Err(Error::NoList),
+            remove(
+                &temp_dir.path().join("nope/.todo"),
+                &markers(),
+                1,
+                &mut writer
+            )
+        );
+        assert_eq!("[ ] Task 2\n", read_to_string(&file_path).unwrap());
+        assert_eq!("Removed: [ ] Task 1\n", String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_edit_and_uncheck_rewrite_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[x] 2022-10-14 Tsak 1\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        edit(&file_path, &markers(), 1, "Task 1", &mut writer).unwrap();
+        uncheck(&file_path, &markers(), 1, &mut writer).unwrap();
+
+        assert_eq!("[ ] Task 1\n", read_to_string(&file_path).unwrap());
+        assert_eq!(
+            "Edited: [x] Task 1\n[ ] Task 1\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_check_recurring_item_adds_next_occurrence() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Water plants rec:3d\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        check(
+            &file_path,
+            &markers(),
+            1,
+            day().and_hms_opt(9, 0, 0).unwrap(),
+            None,
+            &mut writer,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "[x] 2022-10-14 Water plants rec:3d\n[ ] 2022-10-14 Water plants due:2022-10-17 rec:3d\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "[x] Water plants rec:3d\nNext: [ ] Water plants due:2022-10-17 rec:3d\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_track_time_with_start_stop_and_check() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Invoice +billing\n[ ] Review id:1\n").unwrap();
+        let time = |hour, minute| day().and_hms_opt(hour, minute, 0).unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        start(&file_path, &markers(), 1, time(9, 0), &mut writer).unwrap();
+        start(&file_path, &markers(), 2, time(10, 30), &mut writer).unwrap();
+        stop(&file_path, &markers(), time(10, 45), &mut writer).unwrap();
+        start(&file_path, &markers(), 1, time(11, 0), &mut writer).unwrap();
+        check(&file_path, &markers(), 1, time(11, 5), None, &mut writer).unwrap();
+
+        assert_eq!(
+            "Started: Invoice +billing\n\
+             Stopped: Invoice +billing (1h 30m)\nStarted: Review\n\
+             Stopped: Review (0h 15m)\n\
+             Started: Invoice +billing\n\
+             [x] Invoice +billing id:2\nStopped: Invoice +billing (0h 05m)\n",
+            String::from_utf8(writer).unwrap()
+        );
+        assert_eq!(
+            "2 2022-10-14T09:00:00 2022-10-14T10:30:00\n\
+             1 2022-10-14T10:30:00 2022-10-14T10:45:00\n\
+             2 2022-10-14T11:00:00 2022-10-14T11:05:00\n",
+            read_to_string(temp_dir.path().join(".todo.time")).unwrap()
+        );
+        assert_eq!(
+            Err(Error::NotTracking),
+            stop(&file_path, &markers(), time(12, 0), &mut Vec::new())
+        );
+
+        let mut writer = Vec::<u8>::new();
+        report_time(
+            &file_path,
+            &markers(),
+            Grouping::Task,
+            time(12, 0),
+            &mut writer,
+        )
+        .unwrap();
+        assert_eq!(
+            "1h 35m\tInvoice +billing\n0h 15m\tReview\n1h 50m\tTotal\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_list_estimates_and_plan() {
+        let temp_file = NamedTempFile::new(".todo").unwrap();
+        std::fs::write(
+            temp_file.path(),
+            "[ ] Docs +web est:2h\n[ ] Fix crash pri:A est:3h\n[~] Review +web est:1h30m\n[ ] Release\n",
+        )
+        .unwrap();
+
+        let mut writer = Vec::<u8>::new();
+        list_estimates(temp_file.path(), &markers(), &mut writer).unwrap();
+        assert_eq!(
+            "Estimates by status:\n1h 30m\tin-progress\n5h 00m\topen\n\
+             Estimates by tag:\n3h 00m\t(no tag)\n3h 30m\t+web\n",
+            String::from_utf8(writer).unwrap()
+        );
+
+        let mut writer = Vec::<u8>::new();
+        plan(
+            temp_file.path(),
+            &markers(),
+            Estimate::Minutes(300),
+            &mut writer,
+        )
+        .unwrap();
+        assert_eq!(
+            "3 [ ] Fix crash pri:A est:3h\n1 [~] Review +web est:1h30m\nPlanned: 4h 30m of 5h 00m\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_report_estimates_compares_with_tracked_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(
+            file_path.clone(),
+            "[x] Invoice est:2h id:1\n[x] Review est:1h id:2\n[x] Docs est:3 id:3\n[ ] Deploy est:1h id:4\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join(".todo.time"),
+            "1 2022-10-14T09:00:00 2022-10-14T10:00:00\n\
+             1 2022-10-14T11:00:00 2022-10-14T12:30:00\n\
+             2 2022-10-14T13:00:00 2022-10-14T13:30:00\n\
+             3 2022-10-14T14:00:00 2022-10-14T15:00:00\n\
+             4 2022-10-14T15:00:00 2022-10-14T16:00:00\n",
+        )
+        .unwrap();
+        let now = day().and_hms_opt(17, 0, 0).unwrap();
+
+        let mut writer = Vec::<u8>::new();
+        report_estimates(&file_path, &markers(), now, &mut writer).unwrap();
+
+        assert_eq!(
+            "2h 00m\t2h 30m\t125%\tInvoice\n\
+             1h 00m\t0h 30m\t50%\tReview\n\
+             3h 00m\t3h 00m\t100%\tTotal\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_status_and_cancel_tracked_item() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(
+            file_path.clone(),
+            "[ ] Invoice
+[ ] Review
+[ ] Deploy
+",
+        )
+        .unwrap();
+        let time = |hour, minute| day().and_hms_opt(hour, minute, 0).unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        set_status(
+            &file_path,
+            &markers(),
+            3,
+            Status::Waiting,
+            time(9, 0),
+            &mut writer,
+        )
+        .unwrap();
+        start(&file_path, &markers(), 2, time(9, 0), &mut writer).unwrap();
+        set_status(
+            &file_path,
+            &markers(),
+            1,
+            Status::Cancelled,
+            time(9, 20),
+            &mut writer,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "[ ] Invoice\n[-] Review id:1\n[?] Deploy\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "[?] Deploy\nStarted: Review\n[-] Review id:1\nStopped: Review (0h 20m)\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_move_status_to_done_checks_item() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Invoice\n[ ] Review\n").unwrap();
+        let now = day().and_hms_opt(9, 0, 0).unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        move_status(&file_path, &markers(), 2, Status::Waiting, now, &mut writer).unwrap();
+        move_status(
+            &file_path,
+            &markers(),
+            1,
+            Status::Finished,
+            now,
+            &mut writer,
+        )
+        .unwrap();
+        board(&file_path, &markers(), Columns::Status, 72, &mut writer).unwrap();
+
+        assert_eq!(
+            "[?] Review\n[x] Invoice\n\
+             in-progress (0) │ open (0)        │ waiting (1)     │ done (1)\n\
+             ────────────────┼─────────────────┼─────────────────┼────────────────\n\
+             \x20               │                 │ 1 Review        │ 2 Invoice\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_configured_markers_are_read_and_written() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Invoice\n[X] 2022-10-13 Review\n").unwrap();
+        let mut markers = markers();
+        markers.set_built_in("done", 'X');
+        markers.custom.push(CustomStatus {
+            name: "shipped".to_string(),
+            marker: 's',
+            rank: 65,
+            done: true,
+        });
+        let now = day().and_hms_opt(9, 0, 0).unwrap();
+        let shipped = markers.status("shipped").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        move_status(&file_path, &markers, 1, shipped, now, &mut writer).unwrap();
+        list(&file_path, &markers, true, &mut writer).unwrap();
+
+        assert_eq!(
+            "[s] Invoice\n\n1 [X] 2022-10-13 Review\n\n2 [s] 2022-10-14 Invoice\n",
+            String::from_utf8(writer).unwrap()
+        );
+        assert_eq!(
+            "[s] 2022-10-14 Invoice\n[X] 2022-10-13 Review\n",
+            read_to_string(&file_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_log_shows_running_interval_up_to_now() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Invoice id:1\n").unwrap();
+        std::fs::write(
+            temp_dir.path().join(".todo.time"),
+            "1 2022-10-13T23:00:00 2022-10-14T01:00:00\n2 2022-10-14T09:00:00\n",
+        )
+        .unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        log(
+            &file_path,
+            &markers(),
+            day().and_hms_opt(9, 10, 0).unwrap(),
+            &mut writer,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "2022-10-13 23:00 - 2022-10-14 01:00\t2h 00m\tInvoice\n\
+             2022-10-14 09:00 - now\t0h 10m\tid:2\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_commit_msg_checks_referenced_items() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let message_path = temp_dir.path().join("COMMIT_EDITMSG");
+        std::fs::write(file_path.clone(), "[ ] Crash\n[ ] Docs id:4\n[ ] Release\n").unwrap();
+        std::fs::write(&message_path, "Fix crash\n\nTodo: #3, id:4\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        let now = day().and_hms_opt(9, 0, 0).unwrap();
+        commit_msg(&file_path, &markers(), &message_path, now, &mut writer).unwrap();
+
+        assert_eq!(
+            "[ ] Crash\n[x] 2022-10-14 Docs id:4\n[x] 2022-10-14 Release id:5\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "Fix crash\n\nTodo: id:5, id:4\n",
+            read_to_string(&message_path).unwrap()
+        );
+        assert_eq!(
+            "[x] Release id:5\n[x] Docs id:4\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_commit_msg_stops_tracking_and_warns_about_other_items() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let message_path = temp_dir.path().join("COMMIT_EDITMSG");
+        std::fs::write(
+            file_path.clone(),
+            "[ ] Crash id:1\n[ ] Docs id:4\n[ ] Tag after:1 id:6\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join(".todo.time"),
+            "4 2022-10-14T09:00:00\n",
+        )
+        .unwrap();
+        std::fs::write(&message_path, "Docs\n\nTodo: #2, #9, id:6, id:8\n").unwrap();
+        let now = day().and_hms_opt(9, 30, 0).unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        commit_msg(&file_path, &markers(), &message_path, now, &mut writer).unwrap();
+
+        assert_eq!(
+            "[ ] Crash id:1\n[x] 2022-10-14 Docs id:4\n[ ] Tag after:1 id:6\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "Docs\n\nTodo: id:4, #9, id:6, id:8\n",
+            read_to_string(&message_path).unwrap()
+        );
+        assert_eq!(
+            "Warning: #9: There is no item with this index\n\
+             Warning: id:8: There is no item with this index\n\
+             [x] Docs id:4\n\
+             Warning: id:6: The item has to wait for: [ ] Crash id:1\n\
+             Stopped: Docs (0h 30m)\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_note_saves_what_the_editor_wrote() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Crash\n    Seen once\n[ ] Task 2\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        note(
+            &file_path,
+            &markers(),
+            1,
+            "sh -c 'printf \"Steps:\\n1. Run it\\n\\n\" >> \"$0\"'",
+            &mut writer,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "[ ] Crash\n    Seen once\n    Steps:\n    1. Run it\n[ ] Task 2\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!("Noted: [ ] Crash\n", String::from_utf8(writer).unwrap());
+        assert_eq!(
+            Err(Error::EditorFailed),
+            note(&file_path, &markers(), 2, "false", &mut Vec::new())
+        );
+        assert_eq!(
+            "[ ] Crash\n    Seen once\n    Steps:\n    1. Run it\n[ ] Task 2\n",
+            read_to_string(&file_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_show_prints_task_with_notes() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(
+            file_path.clone(),
+            "[x] 2022-10-14 Crash\n    Fixed\n[ ] Task 2\n",
+        )
+        .unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        show(&file_path, &markers(), 2, &mut writer).unwrap();
+        show(&file_path, &markers(), 1, &mut writer).unwrap();
+
+        assert_eq!(
+            "[x] 2022-10-14 Crash\n\nFixed\n[ ] Task 2\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_scan_adds_new_comments() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let source_path = temp_dir.path().join("command.rs");
+        fs::write(
+            &source_path,
+            "// TODO: add a Result as return value\n// TODO: maybe use mark or toggle instead of check\n",
+        )
+        .unwrap();
+        fs::write(
+            &file_path,
+            format!(
+                "[ ] add a Result as return value src:{}:7\n",
+                source_path.display()
+            ),
+        )
+        .unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        scan(
+            &file_path,
+            &markers(),
+            &[temp_dir.path().to_path_buf()],
+            day(),
+            false,
+            &mut "y\n".as_bytes(),
+            &mut writer,
+        )
+        .unwrap();
+
+        assert_eq!(
+            format!(
+                "[ ] add a Result as return value src:{0}:7\n\
+                 [ ] 2022-10-14 maybe use mark or toggle instead of check src:command.rs:2\n",
+                source_path.display()
+            ),
+            read_to_string(&file_path).unwrap()
+        );
+        assert!(String::from_utf8(writer)
+            .unwrap()
+            .ends_with("Added 1 item(s)\n"));
+    }
+
+    #[test]
+    fn test_scan_only_adds_confirmed_comments() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "").unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "// TODO: first\n// TODO: second\n",
+        )
+        .unwrap();
+
+        scan(
+            &file_path,
+            &markers(),
+            &[temp_dir.path().to_path_buf()],
+            day(),
+            false,
+            &mut "n\ny\n".as_bytes(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        let file_content = read_to_string(&file_path).unwrap();
+        assert!(file_content.starts_with("[ ] 2022-10-14 second src:"));
+        assert_eq!(1, file_content.lines().count());
+    }
+
+    #[test]
+    fn test_sync_code() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let source_path = temp_dir.path().join("lib.rs");
+        fs::write(
+            &source_path,
+            "// TODO: can I remove the loops in favor of iterators\nfn main() {}\n// TODO: make unmarking also possible\n",
+        )
+        .unwrap();
+        fs::write(
+            &file_path,
+            format!(
+                "[ ] can I remove the loops in favor of iterators src:{0}:1\n\
+                 [ ] make unmarking also possible src:{0}:1\n\
+                 [ ] replace with a filter function src:{0}:5\n",
+                source_path.display()
+            ),
+        )
+        .unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        sync_code(&file_path, &markers(), false, day(), &mut writer).unwrap();
+
+        assert_eq!(
+            format!(
+                "[ ] can I remove the loops in favor of iterators src:{0}:1\n\
+                 [ ] make unmarking also possible src:{0}:3\n\
+                 [x] 2022-10-14 replace with a filter function src:{0}:5\n",
+                source_path.display()
+            ),
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            format!(
+                "Moved: [ ] make unmarking also possible src:{0}:3\n\
+                 Finished: [x] replace with a filter function src:{0}:5\n",
+                source_path.display()
+            ),
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sync_code_removes_comments_of_checked_items() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let source_path = temp_dir.path().join("lib.rs");
+        fs::write(
+            &source_path,
+            "// TODO: can I remove the loops in favor of iterators\nfn main() {}\n// TODO: make unmarking also possible\n",
+        )
+        .unwrap();
+        fs::write(
+            &file_path,
+            format!(
+                "[x] can I remove the loops in favor of iterators src:{0}:1\n\
+                 [ ] make unmarking also possible src:{0}:3\n",
+                source_path.display()
+            ),
+        )
+        .unwrap();
+
+        sync_code(&file_path, &markers(), true, day(), &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            "fn main() {}\n// TODO: make unmarking also possible\n",
+            read_to_string(&source_path).unwrap()
+        );
+        assert!(read_to_string(&file_path)
+            .unwrap()
+            .ends_with(&format!("src:{}:2\n", source_path.display())));
+    }
+
+    #[test]
+    fn test_stats_as_json() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[x] 2022-10-14 2022-10-10 Task 1\n[ ] Task 2\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        stats(
+            &file_path,
+            &markers(),
+            None,
+            OutputFormat::Json,
+            &mut writer,
+        )
+        .unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+        assert_eq!(2, json["total"]);
+        assert_eq!(50.0, json["completion"]);
+        assert_eq!(4.0, json["average_days_to_complete"]);
+        assert_eq!(1, json["per_week"]["2022-W41"]["added"]);
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[x] Task 1\n[ ] Task 2\n").unwrap();
+        let mut output = Vec::<u8>::new();
+
+        export(
+            &file_path,
+            &markers(),
+            ExportFormat::Markdown,
+            "bugs",
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "## bugs\n\n- [ ] Task 2\n- [x] Task 1\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_export_and_import_ical_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let ical_path = temp_dir.path().join("todo.ics");
+        let content = "[x] 2022-10-14 2022-10-10 Write tests pri:A\n\
+                       [ ] 2022-10-12 Release due:2022-11-01\n\
+                       [?] 2022-10-12 Feedback from Anna\n\
+                       [ ] 2022-10-12 Water plants rec:weekly est:5m id:3\n";
+        fs::write(&file_path, content).unwrap();
+        let mut file = File::create(&ical_path).unwrap();
+        export(
+            &file_path,
+            &markers(),
+            ExportFormat::Ical,
+            "todo",
+            &mut file,
+        )
+        .unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        import(&file_path, &markers(), &ical_path, &mut writer).unwrap();
+
+        assert_eq!(content, read_to_string(&file_path).unwrap());
+        assert_eq!("", String::from_utf8(writer).unwrap());
+    }
+
+    #[test]
+    fn test_import_updates_and_adds_tasks() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        let ical_path = temp_dir.path().join("todo.ics");
+        fs::write(&file_path, "[ ] 2022-10-12 Release src:main.rs:3\n").unwrap();
+        fs::write(
+            &ical_path,
+            "BEGIN:VCALENDAR\r\n\
+             BEGIN:VTODO\r\nSUMMARY:Release\r\nCREATED:20221012T000000Z\r\n\
+             STATUS:COMPLETED\r\nCOMPLETED:20221014T000000Z\r\nEND:VTODO\r\n\
+             BEGIN:VTODO\r\nSUMMARY:Celebrate\r\nEND:VTODO\r\n\
+             END:VCALENDAR\r\n",
+        )
+        .unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        import(&file_path, &markers(), &ical_path, &mut writer).unwrap();
+
+        assert_eq!(
+            "[x] 2022-10-14 2022-10-12 Release src:main.rs:3\n[ ] Celebrate\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "Updated: [x] Release src:main.rs:3\nAdded: [ ] Celebrate\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dedupe_rewrites_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        std::fs::write(file_path.clone(), "[ ] Task 1\n[ ] Task 2\n[ ] task  1\n").unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        dedupe(&file_path, &markers(), 0.9, &mut writer).unwrap();
+
+        assert_eq!(
+            "[ ] Task 1\n[ ] Task 2\n",
+            read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "Merged: [ ] task  1\nRemoved 1 duplicate(s)\n",
+            String::from_utf8(writer).unwrap()
         );
-        temp_file.close().unwrap();
     }
 
     #[test]
@@ -139,7 +2257,7 @@ mod test {
         std::fs::write(file_path.clone(), "[x] Already done\n[ ] Not done yet\n").unwrap();
         let mut writer = Vec::<u8>::new();
 
-        list(&file_path, &mut writer);
+        list(&file_path, &markers(), false, &mut writer).unwrap();
 
         assert_eq!(
             "1 [ ] Not done yet\n\n2 [x] Already done\n",
@@ -0,0 +1,144 @@
+use crate::arg_parsing::Args;
+use clap::CommandFactory;
+use clap_complete::Shell;
+
+/// Subcommands, whose first argument completes to the open items. `uncheck`
+/// is left out, as it takes a closed item.
+const ITEM_COMMANDS: [&str; 15] = [
+    "check",
+    "remove",
+    "edit",
+    "start",
+    "note",
+    "show",
+    "deps",
+    "cancel",
+    "block",
+    "wait",
+    "move-status",
+    "move",
+    "top",
+    "bottom",
+    "mv",
+];
+
+/// Stands for the item commands in the bash and fish snippets
+const COMMANDS_PLACEHOLDER: &str = "{commands}";
+
+const BASH_ITEMS: &str = r#"
+_todo_items() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}" list=() i
+    case "${prev}" in
+        {commands})
+            for ((i = 1; i < COMP_CWORD; i++)); do
+                if [[ "${COMP_WORDS[i]}" == --list ]]; then
+                    list=(--list "${COMP_WORDS[i+1]}")
+                fi
+            done
+            local IFS=$'\n'
+            local items=($(todo "${list[@]}" complete-items 2>/dev/null | grep "^${cur}"))
+            if [[ ${#items[@]} -eq 1 ]]; then
+                COMPREPLY=("${items[0]%%$'\t'*}")
+            else
+                COMPREPLY=("${items[@]/$'\t'/ }")
+            fi
+            return 0
+            ;;
+    esac
+    _todo "$@"
+}
+
+complete -F _todo_items -o bashdefault -o default todo
+"#;
+
+const ZSH_ITEMS: &str = r#"_todo_items() {
+    local -a items list
+    (( ${words[(I)--list]} )) && list=(--list ${words[${words[(I)--list]}+1]})
+    items=(${(f)"$(todo $list complete-items 2>/dev/null)"})
+    _describe -t items 'item' items
+}
+
+"#;
+
+const FISH_ITEMS: &str = r#"
+function __todo_complete_items
+    set -l words (commandline -opc)
+    set -l list
+    if set -l index (contains -i -- --list $words)
+        set list --list $words[(math $index + 1)]
+    end
+    todo $list complete-items 2>/dev/null
+end
+complete -c todo -n "contains -- (commandline -opc)[-1] {commands}" -f -a "(__todo_complete_items)"
+"#;
+
+/// Adds the item completion to the zsh script generated by clap. The first
+/// argument of the item commands gets `_todo_items` as its action.
+fn add_zsh_items(script: &str) -> String {
+    let mut output = String::new();
+    let mut section = "";
+    for line in script.lines() {
+        if let Some(name) = line
+            .strip_prefix('(')
+            .and_then(|line| line.strip_suffix(')'))
+        {
+            section = name;
+        }
+        let is_item_argument =
+            line.starts_with("':item -- ") || line.starts_with("':item-index -- ");
+        match (ITEM_COMMANDS.contains(&section), is_item_argument) {
+            (true, true) => output += &line.replacen(":' \\", ":_todo_items' \\", 1),
+            _ if line == "_todo \"$@\"" => output += &format!("{}{}", ZSH_ITEMS, line),
+            _ => output += line,
+        }
+        output += "\n";
+    }
+    output
+}
+
+/// Prints the completion script for `shell`. In bash, zsh and fish the index
+/// of the commands in `ITEM_COMMANDS` completes to the open items.
+pub fn generate(shell: Shell, writer: &mut impl std::io::Write) {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Args::command(), "todo", &mut script);
+    let script = String::from_utf8(script).unwrap();
+    let script = match shell {
+        Shell::Bash => script + &BASH_ITEMS.replace(COMMANDS_PLACEHOLDER, &ITEM_COMMANDS.join("|")),
+        Shell::Zsh => add_zsh_items(&script),
+        Shell::Fish => script + &FISH_ITEMS.replace(COMMANDS_PLACEHOLDER, &ITEM_COMMANDS.join(" ")),
+        _ => script,
+    };
+    write!(writer, "{}", script).unwrap();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn script(shell: Shell) -> String {
+        let mut output = Vec::new();
+        generate(shell, &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_zsh_completes_items() {
+        let script = script(Shell::Zsh);
+
+        assert!(script.contains("':item-index -- Index of the item to edit:_todo_items' \\\n"));
+        assert!(script.contains("':new-index -- Index the item should have afterwards:' \\\n"));
+        assert_eq!(15, script.matches(":_todo_items'").count());
+        assert!(script.ends_with("_describe -t items 'item' items\n}\n\n_todo \"$@\"\n"));
+    }
+
+    #[test]
+    fn test_bash_and_fish_complete_items() {
+        let bash = script(Shell::Bash);
+        assert!(bash.contains("\n        check|remove|edit|"));
+        assert!(bash.ends_with("complete -F _todo_items -o bashdefault -o default todo\n"));
+        let fish = script(Shell::Fish);
+        assert!(fish.contains("[-1] check remove edit start "));
+        assert!(fish.contains("-a \"(__todo_complete_items)\""));
+        assert!(!script(Shell::Elvish).contains("_todo_items"));
+    }
+}
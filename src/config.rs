@@ -0,0 +1,131 @@
+use crate::status::{CustomStatus, Markers, OTHER_RANK};
+use std::fs;
+use std::path::Path;
+
+/// Per project settings, read from a `.todo.toml` next to the list
+#[derive(Debug, PartialEq, Clone)]
+pub struct Config {
+    /// How similar two task texts have to be (0.0 - 1.0) to count as duplicates
+    pub similarity_threshold: f64,
+    /// Markers of the statuses, from the `[statuses]` table
+    pub markers: Markers,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            similarity_threshold: 0.9,
+            markers: Markers::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(dir: &Path) -> Config {
+        match fs::read_to_string(dir.join(".todo.toml")) {
+            Ok(content) => Config::parse(&content),
+            Err(_) => Config::default(),
+        }
+    }
+
+    fn parse(content: &str) -> Config {
+        let mut config = Config::default();
+        let value = match content.parse::<toml::Value>() {
+            Ok(value) => value,
+            Err(_) => return config,
+        };
+        if let Some(threshold) = value
+            .get("duplicates")
+            .and_then(|duplicates| duplicates.get("threshold"))
+            .and_then(|threshold| threshold.as_float())
+        {
+            config.similarity_threshold = threshold;
+        }
+        if let Some(statuses) = value.get("statuses").and_then(|value| value.as_table()) {
+            for (name, status) in statuses {
+                if let Some(marker) = status.as_str().and_then(single_char) {
+                    config.markers.set_built_in(name, marker);
+                } else if let Some(marker) = status
+                    .get("marker")
+                    .and_then(|marker| marker.as_str())
+                    .and_then(single_char)
+                {
+                    config.markers.custom.push(CustomStatus {
+                        name: name.clone(),
+                        marker,
+                        rank: status
+                            .get("rank")
+                            .and_then(|rank| rank.as_integer())
+                            .and_then(|rank| u32::try_from(rank).ok())
+                            .unwrap_or(OTHER_RANK),
+                        done: status
+                            .get("done")
+                            .and_then(|done| done.as_bool())
+                            .unwrap_or(false),
+                    });
+                }
+            }
+        }
+        config
+    }
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(marker), None) => Some(marker),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_similarity_threshold() {
+        let config = Config::parse("[duplicates]\nthreshold = 0.75\n");
+
+        assert_eq!(0.75, config.similarity_threshold);
+    }
+
+    #[test]
+    fn test_parse_statuses() {
+        let config = Config::parse(
+            "[statuses]\ndone = \"✓\"\nfinished = \"f\"\nwaiting = \"wait\"\n\
+             [statuses.review]\nmarker = \"r\"\nrank = 15\n\
+             [statuses.shipped]\nmarker = \"s\"\ndone = true\n",
+        );
+        let mut markers = Markers::default();
+        markers.set_built_in("done", '✓');
+        markers.custom = vec![
+            CustomStatus {
+                name: "review".to_string(),
+                marker: 'r',
+                rank: 15,
+                done: false,
+            },
+            CustomStatus {
+                name: "shipped".to_string(),
+                marker: 's',
+                rank: OTHER_RANK,
+                done: true,
+            },
+        ];
+
+        assert_eq!(markers, config.markers);
+    }
+
+    #[test]
+    fn test_negative_rank_falls_back_to_default() {
+        let config = Config::parse("[statuses.review]\nmarker = \"r\"\nrank = -1\n");
+
+        assert_eq!(OTHER_RANK, config.markers.custom[0].rank);
+    }
+
+    #[test]
+    fn test_missing_values_fall_back_to_default() {
+        assert_eq!(Config::default(), Config::parse(""));
+        assert_eq!(Config::default(), Config::parse("not [valid toml"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::merge::identities;
+use crate::status::Markers;
+use crate::{similarity, Error, Task, TaskList};
+use std::fmt;
+
+/// How similar the texts of a removed and an added task have to be, to
+/// count as an edit of the same task
+const EDIT_SIMILARITY: f64 = 0.5;
+
+/// The changes between two versions of a list, task by task
+#[derive(Debug, PartialEq, Default)]
+pub struct Diff {
+    pub added: Vec<Task>,
+    pub removed: Vec<Task>,
+    pub completed: Vec<Task>,
+    pub reopened: Vec<Task>,
+    /// The old and the new version of tasks, whose text, due date, priority,
+    /// recurrence, estimate, dependencies or notes changed, or that changed between
+    /// statuses, that aren't done, e.g. from open to cancelled
+    pub edited: Vec<(Task, Task)>,
+    /// The markers the tasks are shown with
+    pub markers: Markers,
+}
+
+fn content(task: &Task) -> impl PartialEq + '_ {
+    (
+        &task.text,
+        task.due,
+        task.priority,
+        task.recurrence,
+        task.estimate,
+        &task.after,
+        &task.notes,
+    )
+}
+
+impl Diff {
+    /// Compares the contents of two versions of a list. Tasks are matched by
+    /// their `id:`, or their creation date and text. A removed and an added
+    /// task with the same creation date and similar texts count as an edit.
+    pub fn new(old: &str, new: &str, markers: &Markers) -> Result<Diff, Error> {
+        let parse = |content: &str| TaskList::parse_with(content, markers).map(|list| list.tasks);
+        let (old, new) = (parse(old)?, parse(new)?);
+        let (old_identities, new_identities) = (identities(&old), identities(&new));
+        let mut diff = Diff {
+            markers: markers.clone(),
+            ..Diff::default()
+        };
+
+        let mut pairs = vec![];
+        let mut added: Vec<&Task> = vec![];
+        for (identity, new_task) in new_identities.iter().zip(new.iter()) {
+            match old_identities.iter().position(|old| old == identity) {
+                Some(position) => pairs.push((&old[position], new_task)),
+                None => added.push(new_task),
+            }
+        }
+        for (identity, old_task) in old_identities.iter().zip(old.iter()) {
+            if new_identities.contains(identity) {
+                continue;
+            }
+            let edited = added
+                .iter()
+                .enumerate()
+                .filter(|(_, new_task)| new_task.created == old_task.created)
+                .map(|(index, new_task)| (index, similarity(&old_task.text, &new_task.text)))
+                .filter(|(_, similarity)| *similarity >= EDIT_SIMILARITY)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b));
+            match edited {
+                Some((index, _)) => pairs.push((old_task, added.remove(index))),
+                None => diff.removed.push(old_task.clone()),
+            }
+        }
+        diff.added = added.into_iter().cloned().collect();
+
+        for (old_task, new_task) in pairs {
+            let status_changed = match (old_task.status.is_done(), new_task.status.is_done()) {
+                (false, true) => {
+                    diff.completed.push(new_task.clone());
+                    false
+                }
+                (true, false) => {
+                    diff.reopened.push(new_task.clone());
+                    false
+                }
+                _ => old_task.status != new_task.status,
+            };
+            if status_changed || content(old_task) != content(new_task) {
+                diff.edited.push((old_task.clone(), new_task.clone()));
+            }
+        }
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.completed.is_empty()
+            && self.reopened.is_empty()
+            && self.edited.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            ("Added", &self.added),
+            ("Completed", &self.completed),
+            ("Reopened", &self.reopened),
+            ("Removed", &self.removed),
+        ];
+        for (title, tasks) in sections {
+            if !tasks.is_empty() {
+                writeln!(f, "{}:", title)?;
+                for task in tasks {
+                    writeln!(f, "  {}", task.without_dates().display_with(&self.markers))?;
+                }
+            }
+        }
+        if !self.edited.is_empty() {
+            writeln!(f, "Edited:")?;
+            for (old_task, new_task) in self.edited.iter() {
+                writeln!(
+                    f,
+                    "  {}",
+                    old_task.without_dates().display_with(&self.markers)
+                )?;
+                writeln!(
+                    f,
+                    "  -> {}",
+                    new_task.without_dates().display_with(&self.markers)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::status::CustomStatus;
+
+    #[test]
+    fn test_diff_of_two_versions() {
+        let old = [
+            "[ ] 2022-10-01 Fix crash",
+            "[ ] 2022-10-01 Relase",
+            "[x] 2022-10-02 2022-10-01 Docs",
+            "[ ] 2022-10-01 Old idea",
+            "[ ] Task id:3",
+            "[ ] 2022-10-01 Rewrite",
+        ]
+        .join("\n");
+        let new = [
+            "[x] 2022-10-14 2022-10-01 Fix crash",
+            "[ ] 2022-10-01 Release pri:A",
+            "[ ] 2022-10-01 Docs",
+            "[ ] 2022-10-14 Write tests",
+            "[x] 2022-10-14 Renamed id:3",
+            "[-] 2022-10-01 Rewrite",
+        ]
+        .join("\n");
+
+        let diff = Diff::new(&old, &new, &Markers::default()).unwrap();
+
+        assert_eq!(
+            "Added:\n  [ ] Write tests\n\
+             Completed:\n  [x] Fix crash\n  [x] Renamed id:3\n\
+             Reopened:\n  [ ] Docs\n\
+             Removed:\n  [ ] Old idea\n\
+             Edited:\n  [ ] Task id:3\n  -> [x] Renamed id:3\n  [ ] Rewrite\n  -> [-] Rewrite\n\
+             \x20 [ ] Relase\n  -> [ ] Release pri:A\n",
+            diff.to_string()
+        );
+        assert!(Diff::new(&old, &old, &Markers::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_custom_done_status_counts_as_completed() {
+        let mut markers = Markers::default();
+        markers.custom.push(CustomStatus {
+            name: "shipped".to_string(),
+            marker: 's',
+            rank: 60,
+            done: true,
+        });
+
+        let diff = Diff::new("[ ] Release\n", "[s] Release\n", &markers).unwrap();
+
+        assert_eq!(1, diff.completed.len());
+        assert!(diff.edited.is_empty());
+        assert_eq!("Completed:\n  [s] Release\n", diff.to_string());
+    }
+}
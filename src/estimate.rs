@@ -0,0 +1,187 @@
+use crate::timelog::format_duration;
+use crate::ParseError;
+use chrono::Duration;
+use std::fmt;
+use std::ops::AddAssign;
+use std::str::FromStr;
+
+/// How much work a task is, stored as `est:<estimate>`. Either story points
+/// (`est:3`) or a duration (`est:2h`, `est:45m`, `est:1h30m`).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Estimate {
+    Points(u32),
+    Minutes(u32),
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Estimate::Points(points) => write!(f, "{}", points),
+            Estimate::Minutes(minutes) if minutes % 60 == 0 => write!(f, "{}h", minutes / 60),
+            Estimate::Minutes(minutes) if minutes < 60 => write!(f, "{}m", minutes),
+            Estimate::Minutes(minutes) => write!(f, "{}h{}m", minutes / 60, minutes % 60),
+        }
+    }
+}
+
+impl FromStr for Estimate {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        if let Ok(points) = input.parse::<u32>() {
+            return Ok(Estimate::Points(points));
+        }
+        let (hours, minutes) = match input.split_once('h') {
+            Some((hours, minutes)) => (hours, minutes),
+            None => ("0", input),
+        };
+        let minutes = match minutes {
+            "" => "0",
+            minutes => minutes.strip_suffix('m').ok_or(ParseError)?,
+        };
+        let hours: u32 = hours.parse().map_err(|_| ParseError)?;
+        let minutes: u32 = minutes.parse().map_err(|_| ParseError)?;
+        // estimates too large to count in minutes stay in the text
+        let total = hours
+            .checked_mul(60)
+            .and_then(|hours| hours.checked_add(minutes))
+            .ok_or(ParseError)?;
+        match total {
+            0 => Err(ParseError),
+            total => Ok(Estimate::Minutes(total)),
+        }
+    }
+}
+
+impl Estimate {
+    /// Whether both estimates are points or both are durations
+    pub fn same_unit(&self, other: &Estimate) -> bool {
+        matches!(
+            (self, other),
+            (Estimate::Points(_), Estimate::Points(_))
+                | (Estimate::Minutes(_), Estimate::Minutes(_))
+        )
+    }
+
+    fn value(&self) -> u32 {
+        match *self {
+            Estimate::Points(value) | Estimate::Minutes(value) => value,
+        }
+    }
+}
+
+/// Sum of several estimates. Points and durations can't be added up, so
+/// they are summed separately. Sums, that don't fit, stop at the maximum.
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct Total {
+    pub points: u32,
+    pub minutes: u32,
+}
+
+impl AddAssign<Estimate> for Total {
+    fn add_assign(&mut self, estimate: Estimate) {
+        match estimate {
+            Estimate::Points(points) => self.points = self.points.saturating_add(points),
+            Estimate::Minutes(minutes) => self.minutes = self.minutes.saturating_add(minutes),
+        }
+    }
+}
+
+/// `5 points, 3h 30m`
+impl fmt::Display for Total {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if self.points > 0 {
+            parts.push(format!("{} points", self.points));
+        }
+        if self.minutes > 0 || self.points == 0 {
+            parts.push(format_duration(Duration::minutes(self.minutes as i64)));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Picks estimates from `estimates` in their order, as long as they fit
+/// into what is left of `capacity`. Estimates in another unit than the
+/// capacity are skipped. Returns the positions of the picked estimates.
+pub fn fill(capacity: Estimate, estimates: &[Option<Estimate>]) -> Vec<usize> {
+    let mut left = capacity.value();
+    let mut picked = vec![];
+    for (position, estimate) in estimates.iter().enumerate() {
+        match estimate {
+            Some(estimate) if estimate.same_unit(&capacity) && estimate.value() <= left => {
+                left -= estimate.value();
+                picked.push(position);
+            }
+            _ => {}
+        }
+    }
+    picked
+}
+
+/// How the tracked time compares to the estimate, e.g. `125%` for a task,
+/// that took a quarter longer than estimated
+pub fn accuracy(estimated_minutes: u32, tracked: Duration) -> String {
+    match estimated_minutes {
+        0 => "-".to_string(),
+        minutes => format!(
+            "{:.0}%",
+            tracked.num_minutes() as f64 * 100.0 / minutes as f64
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        for estimate in ["3", "2h", "45m", "1h30m"] {
+            assert_eq!(estimate, estimate.parse::<Estimate>().unwrap().to_string());
+        }
+        assert_eq!(Estimate::Minutes(90), "1h30m".parse().unwrap());
+        assert_eq!("2h", "120m".parse::<Estimate>().unwrap().to_string());
+        for estimate in ["", "0h", "2x", "h", "1.5h", "30m2h"] {
+            assert!(estimate.parse::<Estimate>().is_err(), "{}", estimate);
+        }
+    }
+
+    #[test]
+    fn test_total_keeps_points_and_durations_apart() {
+        let mut total = Total::default();
+        assert_eq!("0h 00m", total.to_string());
+
+        total += Estimate::Points(3);
+        total += Estimate::Minutes(90);
+        total += Estimate::Points(2);
+
+        assert_eq!("5 points, 1h 30m", total.to_string());
+
+        total += Estimate::Points(u32::MAX);
+        assert_eq!(u32::MAX, total.points);
+    }
+
+    #[test]
+    fn test_estimate_too_large_for_minutes_is_no_estimate() {
+        assert_eq!(Estimate::Minutes(4294967280), "71582788h".parse().unwrap());
+        for estimate in ["99999999h", "71582788h16m"] {
+            assert!(estimate.parse::<Estimate>().is_err(), "{}", estimate);
+        }
+    }
+
+    #[test]
+    fn test_fill_skips_what_doesnt_fit() {
+        let estimates = [
+            Some(Estimate::Minutes(240)),
+            Some(Estimate::Points(1)),
+            None,
+            Some(Estimate::Minutes(180)),
+            Some(Estimate::Minutes(120)),
+        ];
+
+        assert_eq!(vec![0, 4], fill(Estimate::Minutes(360), &estimates));
+        assert_eq!(vec![1], fill(Estimate::Points(5), &estimates));
+        assert_eq!("125%", accuracy(120, Duration::minutes(150)));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{ParseError, Task, TaskList};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Html,
+    Markdown,
+    Ical,
+}
+
+impl FromStr for ExportFormat {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        match input {
+            "csv" => Ok(ExportFormat::Csv),
+            "html" => Ok(ExportFormat::Html),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "ics" | "ical" => Ok(ExportFormat::Ical),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+/// A task together with the index it's displayed with
+struct Entry<'a> {
+    index: usize,
+    task: &'a Task,
+}
+
+fn entries(task_list: &TaskList) -> Vec<Entry<'_>> {
+    task_list
+        .display_order()
+        .into_iter()
+        .enumerate()
+        .map(|(index, position)| Entry {
+            index: index + 1,
+            task: &task_list.tasks[position],
+        })
+        .collect()
+}
+
+/// Renders the list in the given format. `title` is used as heading, where
+/// the format has one.
+pub(crate) fn export(task_list: &TaskList, format: ExportFormat, title: &str) -> String {
+    let entries = entries(task_list);
+    match format {
+        ExportFormat::Csv => csv(&entries),
+        ExportFormat::Html => html(&entries, title),
+        ExportFormat::Markdown => markdown(&entries, title),
+        ExportFormat::Ical => {
+            let tasks: Vec<&Task> = entries.iter().map(|entry| entry.task).collect();
+            crate::ical::to_ical(&tasks)
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv(entries: &[Entry]) -> String {
+    let mut output = String::from("index,status,text,created,completed,source\n");
+    for entry in entries {
+        let task = entry.task;
+        let fields = [
+            entry.index.to_string(),
+            task.status.name(),
+            task.text.clone(),
+            task.created
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+            task.completed
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+            task.source
+                .as_ref()
+                .map(|source| source.to_string())
+                .unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        output += &fields.join(",");
+        output += "\n";
+    }
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; max-width: 40em; margin: 2em auto; }
+li { list-style: none; margin: 0.3em 0; }
+li.done { color: #888; text-decoration: line-through; }
+.date { color: #888; font-size: 0.8em; margin-left: 0.5em; }";
+
+fn html(entries: &[Entry], title: &str) -> String {
+    let mut output = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n\
+         <style>\n{1}\n</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape_html(title),
+        HTML_STYLE
+    );
+    for (heading, finished) in [("Open", false), ("Done", true)] {
+        output += &format!("<h2>{}</h2>\n<ul>\n", heading);
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.task.status.is_closed() == finished)
+        {
+            let task = entry.task;
+            let date = match (task.completed, task.created) {
+                (Some(completed), _) if finished => {
+                    format!(" <span class=\"date\">{}</span>", completed)
+                }
+                (_, Some(created)) => format!(" <span class=\"date\">{}</span>", created),
+                _ => String::new(),
+            };
+            output += &format!(
+                "<li class=\"{}\"><input type=\"checkbox\" disabled{}> {}{}</li>\n",
+                if finished { "done" } else { "open" },
+                if finished { " checked" } else { "" },
+                escape_html(&task.text),
+                date
+            );
+        }
+        output += "</ul>\n";
+    }
+    output += "</body>\n</html>\n";
+    output
+}
+
+fn markdown(entries: &[Entry], title: &str) -> String {
+    let mut output = format!("## {}\n\n", title);
+    for entry in entries {
+        let checkbox = match entry.task.status.is_closed() {
+            true => "[x]",
+            false => "[ ]",
+        };
+        output += &format!("- {} {}\n", checkbox, entry.task.text);
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn task_list() -> TaskList {
+        "[x] 2022-10-14 2022-10-10 Write \"tests\", again\n[ ] 2022-10-12 Use <b>bold</b> & more"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_export_csv() {
+        assert_eq!(
+            "index,status,text,created,completed,source\n\
+             1,open,Use <b>bold</b> & more,2022-10-12,,\n\
+             2,done,\"Write \"\"tests\"\", again\",2022-10-10,2022-10-14,\n",
+            export(&task_list(), ExportFormat::Csv, "todo")
+        );
+    }
+
+    #[test]
+    fn test_export_html() {
+        let html = export(&task_list(), ExportFormat::Html, "todo");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h1>todo</h1>"));
+        assert!(html.contains(
+            "<h2>Open</h2>\n<ul>\n<li class=\"open\"><input type=\"checkbox\" disabled> \
+             Use &lt;b&gt;bold&lt;/b&gt; &amp; more <span class=\"date\">2022-10-12</span></li>\n</ul>"
+        ));
+        assert!(html.contains(
+            "<h2>Done</h2>\n<ul>\n<li class=\"done\"><input type=\"checkbox\" disabled checked> \
+             Write &quot;tests&quot;, again <span class=\"date\">2022-10-14</span></li>\n</ul>"
+        ));
+    }
+
+    #[test]
+    fn test_export_ical_in_display_order() {
+        let ical = export(&task_list(), ExportFormat::Ical, "todo");
+
+        let summaries: Vec<&str> = ical
+            .lines()
+            .filter(|line| line.starts_with("SUMMARY:"))
+            .collect();
+        assert_eq!(
+            vec![
+                "SUMMARY:Use <b>bold</b> & more",
+                "SUMMARY:Write \"tests\"\\, again"
+            ],
+            summaries
+        );
+    }
+
+    #[test]
+    fn test_export_markdown() {
+        assert_eq!(
+            "## todo\n\n- [ ] Use <b>bold</b> & more\n- [x] Write \"tests\", again\n",
+            export(&task_list(), ExportFormat::Markdown, "todo")
+        );
+    }
+
+    #[test]
+    fn test_cancelled_items_are_closed_in_every_format() {
+        let task_list = "[-] Rewrite in Go".parse().unwrap();
+
+        assert_eq!(
+            "## todo\n\n- [x] Rewrite in Go\n",
+            export(&task_list, ExportFormat::Markdown, "todo")
+        );
+        assert!(export(&task_list, ExportFormat::Html, "todo")
+            .contains("<h2>Done</h2>\n<ul>\n<li class=\"done\">"));
+    }
+}
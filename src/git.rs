@@ -0,0 +1,162 @@
+use crate::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Key of the commit message trailer, that refers to items, e.g. `Todo: #3`
+const TRAILER: &str = "Todo:";
+
+/// Lines of `.gitattributes`, that let `todo merge-driver` merge the lists
+pub const MERGE_ATTRIBUTES: [&str; 3] = [
+    ".todo merge=todo",
+    ".todo.d/*.todo merge=todo",
+    "/*.todo merge=todo",
+];
+
+/// An item referred to in a commit message: `#3` is the item with index 3,
+/// `id:7` the item tagged `id:7`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reference {
+    Index(usize),
+    Id(u32),
+}
+
+/// Written like in the commit message
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reference::Index(index) => write!(f, "#{}", index),
+            Reference::Id(id) => write!(f, "id:{}", id),
+        }
+    }
+}
+
+impl Reference {
+    fn parse(word: &str) -> Option<Reference> {
+        match (word.strip_prefix('#'), word.strip_prefix("id:")) {
+            (Some(index), _) => index.parse().ok().map(Reference::Index),
+            (_, Some(id)) => id.parse().ok().map(Reference::Id),
+            _ => None,
+        }
+    }
+}
+
+/// Items referred to by the `Todo:` trailers of a commit message. Several
+/// items can be listed in one trailer, separated by commas or spaces.
+pub fn references(message: &str) -> Vec<Reference> {
+    message
+        .lines()
+        .filter_map(|line| line.strip_prefix(TRAILER))
+        .flat_map(|value| value.split([',', ' ']))
+        .filter_map(Reference::parse)
+        .collect()
+}
+
+/// Replaces `#<index>` in the `Todo:` trailers with the `id:` of the item,
+/// so that the commit keeps pointing to it when the list changes
+pub fn rewrite_references(message: &str, id_of: impl Fn(usize) -> Option<u32>) -> String {
+    let mut rewritten = String::new();
+    for line in message.split_inclusive('\n') {
+        match line.strip_prefix(TRAILER) {
+            Some(value) => {
+                let words: Vec<String> = value
+                    .trim_end()
+                    .split([',', ' '])
+                    .filter(|word| !word.is_empty())
+                    .map(|word| match Reference::parse(word) {
+                        Some(Reference::Index(index)) => match id_of(index) {
+                            Some(id) => format!("id:{}", id),
+                            None => word.to_string(),
+                        },
+                        _ => word.to_string(),
+                    })
+                    .collect();
+                rewritten += &format!("{} {}", TRAILER, words.join(", "));
+                rewritten += &line[line.trim_end().len()..];
+            }
+            None => rewritten += line,
+        }
+    }
+    rewritten
+}
+
+fn git(dir: &Path, arguments: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git")
+        .args(arguments)
+        .current_dir(dir)
+        .output()
+        .map_err(|_| Error::NoRepository)?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        false => Err(Error::NoRepository),
+    }
+}
+
+/// Sets `key` in the config of the repository containing `dir`
+pub fn set_config(dir: &Path, key: &str, value: &str) -> Result<(), Error> {
+    git(dir, &["config", key, value])?;
+    Ok(())
+}
+
+/// SHA of the commit checked out in the repository containing `dir`
+pub fn head(dir: &Path) -> Result<String, Error> {
+    Ok(git(dir, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Content of the file at `path`, relative to `dir`, in the revision `rev`.
+/// `None` if the file didn't exist in that revision.
+pub fn file_at(dir: &Path, rev: &str, path: &Path) -> Result<Option<String>, Error> {
+    let commit = format!("{}^{{commit}}", rev);
+    git(dir, &["rev-parse", "--verify", "--quiet", &commit])
+        .map_err(|_| Error::UnknownRevision(rev.to_string()))?;
+    let path = path.strip_prefix(".").unwrap_or(path);
+    Ok(git(dir, &["show", &format!("{}:./{}", rev, path.display())]).ok())
+}
+
+/// Hooks directory of the repository containing `dir`
+pub fn hooks_dir(dir: &Path) -> Result<PathBuf, Error> {
+    let hooks = git(dir, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(dir.join(hooks.trim()))
+}
+
+/// The `commit-msg` hook, that runs `todo git-hook commit-msg` with the
+/// message file
+pub fn commit_msg_hook(executable: &Path, list_name: Option<&str>) -> String {
+    let list = match list_name {
+        Some(name) => format!(" --list {}", shell_words::quote(name)),
+        None => String::new(),
+    };
+    format!(
+        "#!/bin/sh\n# Installed by todo git-hook install\nexec {}{} git-hook commit-msg \"$1\"\n",
+        shell_words::quote(&executable.to_string_lossy()),
+        list
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_references_in_trailers() {
+        let message = "Fix crash on empty input\n\nTodo: #3, id:7\nTodo: #12\nSee #4\n";
+
+        assert_eq!(
+            vec![Reference::Index(3), Reference::Id(7), Reference::Index(12)],
+            references(message)
+        );
+        assert_eq!(
+            "Fix crash on empty input\n\nTodo: id:9, id:7\nTodo: #12\nSee #4\n",
+            rewrite_references(message, |index| (index == 3).then_some(9))
+        );
+    }
+
+    #[test]
+    fn test_hook_runs_todo_with_list() {
+        assert_eq!(
+            "#!/bin/sh\n# Installed by todo git-hook install\n\
+             exec '/opt/my tools/todo' --list bugs git-hook commit-msg \"$1\"\n",
+            commit_msg_hook(Path::new("/opt/my tools/todo"), Some("bugs"))
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{ParseError, Status, Task};
+use chrono::NaiveDate;
+
+const DATE_FORMAT: &str = "%Y%m%d";
+
+/// Escapes a TEXT value as described in RFC 5545
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(escaped) => unescaped.push(escaped),
+                None => {}
+            },
+            (c, false) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Splits lines longer than 75 bytes into continuation lines
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded + "\r\n"
+}
+
+fn unfold(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in input.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Identifies a task across exports, so that calendar clients update
+/// instead of duplicating it. FNV-1a, because it's stable across builds.
+fn uid(task: &Task) -> String {
+    let created = task
+        .created
+        .map(|date| date.to_string())
+        .unwrap_or_default();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in created.bytes().chain(task.text.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}@todo-rs", hash)
+}
+
+fn date_time(date: NaiveDate) -> String {
+    format!("{}T000000Z", date.format(DATE_FORMAT))
+}
+
+/// iCalendar priorities go from 1 (highest) to 9, todo priorities from A to Z
+fn ical_priority(priority: char) -> u8 {
+    (priority as u8 - b'A' + 1).min(9)
+}
+
+/// STATUS of a task. Custom statuses, that count as done, are exported as
+/// COMPLETED, other statuses iCalendar doesn't know, like waiting or blocked,
+/// as NEEDS-ACTION.
+pub(crate) fn ical_status(status: &Status) -> &'static str {
+    match status {
+        status if status.is_done() => "COMPLETED",
+        Status::Cancelled => "CANCELLED",
+        Status::InProgress => "IN-PROCESS",
+        _ => "NEEDS-ACTION",
+    }
+}
+
+pub(crate) fn to_ical(tasks: &[&Task]) -> String {
+    let mut output =
+        String::from("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//todo-rs//todo//EN\r\n");
+    for task in tasks {
+        let mut lines = vec!["BEGIN:VTODO".to_string(), format!("UID:{}", uid(task))];
+        // DTSTAMP is mandatory, it doesn't use the current time to keep
+        // exports of an unchanged list identical
+        let stamp = task.completed.or(task.created).unwrap_or_default();
+        lines.push(format!("DTSTAMP:{}", date_time(stamp)));
+        lines.push(format!("SUMMARY:{}", escape(&task.text)));
+        if let Some(created) = task.created {
+            lines.push(format!("CREATED:{}", date_time(created)));
+        }
+        lines.push(format!("STATUS:{}", ical_status(&task.status)));
+        if let (true, Some(completed)) = (task.status.is_done(), task.completed) {
+            lines.push(format!("COMPLETED:{}", date_time(completed)));
+        }
+        if let Some(due) = task.due {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format(DATE_FORMAT)));
+        }
+        if let Some(priority) = task.priority {
+            lines.push(format!("PRIORITY:{}", ical_priority(priority)));
+        }
+        lines.push("END:VTODO".to_string());
+        for line in lines {
+            output += &fold(&line);
+        }
+    }
+    output += "END:VCALENDAR\r\n";
+    output
+}
+
+/// Dates and date-times both start with `YYYYMMDD`
+fn parse_date(value: &str) -> Result<NaiveDate, ParseError> {
+    value
+        .get(0..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, DATE_FORMAT).ok())
+        .ok_or(ParseError)
+}
+
+/// Reads all `VTODO` components of an iCalendar file
+pub fn from_ical(input: &str) -> Result<Vec<Task>, ParseError> {
+    let mut tasks = vec![];
+    let mut current: Option<Task> = None;
+    for line in unfold(input) {
+        let (name, value) = match line.split_once(':') {
+            Some(property) => property,
+            None => continue,
+        };
+        // parameters like `;VALUE=DATE` don't matter for our purposes
+        let name = name.split(';').next().unwrap().to_ascii_uppercase();
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value == "VTODO" => current = Some(Task::default()),
+            ("END", Some(_)) if value == "VTODO" => tasks.push(current.take().unwrap()),
+            // a task is a single line in the list
+            ("SUMMARY", Some(task)) => {
+                task.text = unescape(value)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            }
+            ("STATUS", Some(task)) => match value {
+                "COMPLETED" => task.status = Status::Finished,
+                "CANCELLED" => task.status = Status::Cancelled,
+                "IN-PROCESS" => task.status = Status::InProgress,
+                _ => {}
+            },
+            ("CREATED", Some(task)) => task.created = Some(parse_date(value)?),
+            ("COMPLETED", Some(task)) => {
+                task.completed = Some(parse_date(value)?);
+                task.status = Status::Finished;
+            }
+            ("DUE", Some(task)) => task.due = Some(parse_date(value)?),
+            ("PRIORITY", Some(task)) => {
+                task.priority = match value.parse::<u8>().map_err(|_| ParseError)? {
+                    0 => None,
+                    priority => Some((b'A' + priority.min(9) - 1) as char),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_export_vtodo() {
+        let task = "[x] 2022-10-14 2022-10-10 Write tests, again pri:A due:2022-10-20"
+            .parse::<Task>()
+            .unwrap();
+
+        assert_eq!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//todo-rs//todo//EN\r\n\
+             BEGIN:VTODO\r\n\
+             UID:322bd97da86fc779@todo-rs\r\n\
+             DTSTAMP:20221014T000000Z\r\n\
+             SUMMARY:Write tests\\, again\r\n\
+             CREATED:20221010T000000Z\r\n\
+             STATUS:COMPLETED\r\n\
+             COMPLETED:20221014T000000Z\r\n\
+             DUE;VALUE=DATE:20221020\r\n\
+             PRIORITY:1\r\n\
+             END:VTODO\r\n\
+             END:VCALENDAR\r\n",
+            to_ical(&[&task])
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tasks: Vec<Task> = [
+            "[ ] 2022-10-10 Release pri:C due:2022-11-01",
+            "[x] 2022-10-14 2022-10-10 Write tests; a long text with a backslash \\ \
+             that needs to be folded over several lines, because it's so long",
+            "[ ] Without any dates",
+            "[~] 2022-10-12 In progress",
+            "[-] 2022-10-12 Cancelled",
+        ]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect();
+
+        let ical = to_ical(&tasks.iter().collect::<Vec<&Task>>());
+
+        assert!(ical.lines().all(|line| line.len() <= 76));
+        assert_eq!(tasks, from_ical(&ical).unwrap());
+    }
+
+    #[test]
+    fn test_import_from_calendar_client() {
+        let ical = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Not a task\nEND:VEVENT\n\
+                    BEGIN:VTODO\nUID:123\nSUMMARY:Call\n  Greg\\nat 9\nDUE:20221020T120000Z\n\
+                    PRIORITY:5\nCOMPLETED:20221019T080000Z\nEND:VTODO\nEND:VCALENDAR\n";
+
+        assert_eq!(
+            vec!["[x] 2022-10-19 Call Greg at 9 pri:E due:2022-10-20"
+                .parse::<Task>()
+                .unwrap()],
+            from_ical(ical).unwrap()
+        );
+    }
+}
@@ -1,174 +1,259 @@
-use crate::task::Task;
+use crate::estimate::{Estimate, Total};
+use crate::status::Markers;
+pub use crate::status::Status;
+pub use crate::task::{Source, Task, TaskDisplay};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::result::Result;
 use std::str::FromStr;
 
+pub mod board;
 pub mod command;
-mod task {
-    use super::ParseError;
-    use crate::Status;
-    use std::fmt;
-    use std::str::FromStr;
-
-    #[derive(Debug, PartialEq, Clone)]
-    pub struct Task {
-        pub text: String,
-        pub status: Status,
-    }
-    impl fmt::Display for Task {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f, "{} {}", self.status, self.text)
-        }
-    }
+pub mod config;
+pub mod diff;
+pub mod estimate;
+pub mod export;
+pub mod git;
+pub mod ical;
+pub mod merge;
+pub mod recurrence;
+pub mod scan;
+pub mod session;
+pub mod stats;
+pub mod status;
+mod task;
+pub mod timelog;
+pub mod ui;
 
-    impl Task {
-        pub fn is_finished(&self) -> bool {
-            match self.status {
-                Status::Finished => true,
-                Status::Unfinished => false,
-            }
-        }
+#[derive(Debug)]
+pub struct ParseError;
 
-        pub fn check(&mut self) -> Task {
-            self.status = Status::Finished;
-            self.clone()
-        }
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid format")
     }
-    impl FromStr for Task {
-        type Err = ParseError;
-
-        fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
-            let status = match &input[0..3] {
-                "[ ]" => Status::Unfinished,
-                "[x]" => Status::Finished,
-                _ => return Err(ParseError),
-            };
-            let text = &input[4..input.len()];
+}
 
-            Ok(Task {
-                text: text.to_string(),
-                status,
-            })
-        }
-    }
+impl std::error::Error for ParseError {}
 
-    #[cfg(test)]
-    mod test {
-        use crate::Status;
-        use crate::Task;
+/// Reference to an item as given on the command line. Either just the index
+/// on the current list or `path:index` to address an item on any list.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TaskRef {
+    pub path: Option<std::path::PathBuf>,
+    pub index: usize,
+}
 
-        #[test]
-        fn test_is_finished() {
-            let task1 = Task {
-                text: "Do this task first".to_string(),
-                status: Status::Finished,
-            };
-            let task2 = Task {
-                text: "Then do this task".to_string(),
-                status: Status::Unfinished,
-            };
+impl FromStr for TaskRef {
+    type Err = ParseError;
 
-            assert!(task1.is_finished());
-            assert!(!task2.is_finished());
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        match input.rsplit_once(':') {
+            Some((path, index)) if !path.is_empty() => Ok(TaskRef {
+                path: Some(path.into()),
+                index: index.parse().map_err(|_| ParseError)?,
+            }),
+            _ => Ok(TaskRef {
+                path: None,
+                index: input.parse().map_err(|_| ParseError)?,
+            }),
         }
+    }
+}
 
-        #[test]
-        fn test_parse_unfinished_task() {
-            let task = "[ ] Make things work"
-                .parse::<Task>()
-                .expect("parsing failed");
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    FileExists,
+    IndexOutOfBounds,
+    NoList,
+    Duplicate(Box<Task>),
+    InvalidCalendar,
+    InvalidTimeLog,
+    NotTracking,
+    EditorFailed,
+    NoRepository,
+    HookExists,
+    MergeConflicts(usize),
+    UnknownRevision(String),
+    Blocked(Vec<Task>),
+    UnknownStatus(String),
+    InvalidListName(String),
+    SameList,
+    UnresolvedConflict,
+    /// A line of the list, that is neither an item nor a note, by its number
+    InvalidItem(usize),
+    /// A file to write to, e.g. of `todo export --output`, by its path
+    CannotCreate(String),
+}
 
-            assert_eq!(
-                Task {
-                    text: "Make things work".to_string(),
-                    status: Status::Unfinished
-                },
-                task
-            );
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::FileExists => write!(f, "\".todo\" already exists"),
+            Error::IndexOutOfBounds => write!(f, "There is no item with this index"),
+            Error::NoList => write!(f, "There is no list in this directory"),
+            Error::InvalidCalendar => write!(f, "Couldn't read the iCalendar file"),
+            Error::InvalidTimeLog => write!(f, "Couldn't read the time log"),
+            Error::NotTracking => write!(f, "No item is being tracked"),
+            Error::EditorFailed => write!(f, "The editor exited with an error"),
+            Error::NoRepository => write!(f, "This is not inside a git repository"),
+            Error::HookExists => write!(f, "Another commit-msg hook is already installed"),
+            Error::UnknownRevision(rev) => write!(f, "Unknown revision: {}", rev),
+            Error::UnknownStatus(name) => write!(f, "There is no status called \"{}\"", name),
+            Error::InvalidListName(name) => write!(f, "\"{}\" is not a valid list name", name),
+            Error::SameList => write!(f, "The item is already on this list"),
+            Error::UnresolvedConflict => write!(f, "The list has unresolved merge conflicts"),
+            Error::InvalidItem(line) => write!(f, "Line {} of the list is not an item", line),
+            Error::CannotCreate(path) => write!(f, "Couldn't create {}", path),
+            Error::Blocked(blockers) => {
+                let blockers: Vec<String> = blockers
+                    .iter()
+                    .map(|task| task.without_dates().to_string())
+                    .collect();
+                write!(f, "The item has to wait for: {}", blockers.join(", "))
+            }
+            Error::MergeConflicts(count) => {
+                write!(f, "{} item(s) were changed on both sides", count)
+            }
+            Error::Duplicate(task) => write!(
+                f,
+                "A similar item is already on the list: {}",
+                task.without_dates()
+            ),
         }
+    }
+}
 
-        #[test]
-        fn test_parse_finished_task() {
-            let task = "[x] Nothing special"
-                .parse::<Task>()
-                .expect("parsing failed");
+/// Lowercases the text and collapses all whitespace, so that trivial
+/// differences don't make two tasks look different
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
 
-            assert_eq!(
-                Task {
-                    text: "Nothing special".to_string(),
-                    status: Status::Finished
-                },
-                task
+fn levenshtein_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current_row = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution_cost = if a_char == b_char { 0 } else { 1 };
+            current_row.push(
+                (previous_row[j] + substitution_cost)
+                    .min(previous_row[j + 1] + 1)
+                    .min(current_row[j] + 1),
             );
         }
+        previous_row = current_row;
+    }
+    previous_row[b.len()]
+}
 
-        #[test]
-        fn test_finished_task_to_string() {
-            let task = Task {
-                text: "Get coffee".to_string(),
-                status: Status::Finished,
-            };
-
-            assert_eq!("[x] Get coffee", task.to_string());
-        }
-
-        #[test]
-        fn test_unfinished_task_to_string() {
-            let task = Task {
-                text: "Get coffee".to_string(),
-                status: Status::Unfinished,
-            };
-
-            assert_eq!("[ ] Get coffee", task.to_string());
-        }
+/// Similarity of two task texts between 0.0 (nothing in common) and 1.0
+/// (identical after normalization)
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = normalize(a).chars().collect::<Vec<char>>();
+    let b = normalize(b).chars().collect::<Vec<char>>();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
     }
+    1.0 - levenshtein_distance(&a, &b) as f64 / longest as f64
 }
 
-#[derive(Debug)]
-pub struct ParseError;
+/// Numbers in a text, like the parts of a version
+fn numbers(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|number| !number.is_empty())
+        .collect()
+}
 
-#[derive(Debug, PartialEq)]
-pub enum Error {
-    FileExists,
-    IndexOutOfBounds,
+/// Whether two task texts are at least `threshold` similar. Texts with
+/// different numbers, like `Release v1.2.4` and `Release v1.2.5`, never are.
+fn is_duplicate(a: &str, b: &str, threshold: f64) -> bool {
+    numbers(a) == numbers(b) && similarity(a, b) >= threshold
 }
 
-#[derive(PartialEq, Debug, Clone)]
-pub enum Status {
-    Unfinished,
-    Finished,
+/// Indentation of the lines of notes below their task
+const NOTE_INDENT: &str = "    ";
+
+/// Marks tasks with notes in listings
+const NOTES_MARKER: &str = " (notes)";
+
+/// Marks tasks, that wait for other tasks, in listings
+const BLOCKED_MARKER: &str = " (blocked)";
+
+/// Group of tasks in a listing. The groups are displayed by the rank of
+/// their status.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+struct Section {
+    rank: u32,
+    closed: bool,
 }
 
-impl fmt::Display for Status {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let representation = match self {
-            Status::Unfinished => "[ ]",
-            Status::Finished => "[x]",
-        };
-        write!(f, "{}", representation)
-    }
+/// Lines git writes around the two sides of a merge conflict
+const CONFLICT_MARKERS: [&str; 3] = ["<<<<<<<", "=======", ">>>>>>>"];
+
+fn strip_note_indent(line: &str) -> &str {
+    line.strip_prefix(NOTE_INDENT)
+        .or_else(|| line.strip_prefix('\t'))
+        .unwrap_or_else(|| line.trim_start())
 }
 
 #[derive(PartialEq, Debug)]
 struct TaskList {
     tasks: Vec<Task>,
+    /// The markers the list is read and written with
+    markers: Markers,
 }
 
 impl FromStr for TaskList {
     type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
-        let tasks = input
-            .lines()
-            .map(|line| line.parse::<Task>().unwrap())
-            .collect();
-        Ok(TaskList { tasks })
+        TaskList::parse_with(input, &Markers::default()).map_err(|_| ParseError)
     }
 }
 
 impl TaskList {
     fn new() -> Self {
-        TaskList { tasks: vec![] }
+        TaskList {
+            tasks: vec![],
+            markers: Markers::default(),
+        }
+    }
+
+    /// Reads a list written with the markers of a project. Lists, that
+    /// still contain the markers of a merge conflict, are refused.
+    fn parse_with(input: &str, markers: &Markers) -> Result<Self, Error> {
+        let mut tasks: Vec<Task> = vec![];
+        for (number, line) in input.lines().enumerate() {
+            let is_blank = line.trim().is_empty();
+            match (is_blank || line.starts_with([' ', '\t']), tasks.last_mut()) {
+                (true, Some(task)) => task.notes.push(strip_note_indent(line).to_string()),
+                (true, None) if is_blank => {}
+                _ if CONFLICT_MARKERS
+                    .iter()
+                    .any(|marker| line.starts_with(marker)) =>
+                {
+                    return Err(Error::UnresolvedConflict)
+                }
+                _ => tasks.push(
+                    Task::parse_with(line, markers).map_err(|_| Error::InvalidItem(number + 1))?,
+                ),
+            }
+        }
+        for task in tasks.iter_mut() {
+            while task.notes.last().is_some_and(|note| note.trim().is_empty()) {
+                task.notes.pop();
+            }
+        }
+        Ok(TaskList {
+            tasks,
+            markers: markers.clone(),
+        })
     }
 
     fn add(&mut self, new_task: Task) -> &mut Self {
@@ -180,98 +265,424 @@ impl TaskList {
     fn finished_tasks(&self) -> Vec<Task> {
         self.tasks
             .iter()
-            .filter(|x| x.is_finished())
+            .filter(|x| x.status.is_done())
             .map(|x| x.to_owned())
             .collect()
     }
 
+    /// Tasks, that aren't finished or cancelled
     fn unfinished_tasks(&self) -> Vec<Task> {
         self.tasks
             .iter()
-            .filter(|x| !x.is_finished())
+            .filter(|x| !x.status.is_closed())
             .map(|x| x.to_owned())
             .collect()
     }
 
+    /// Finds an unfinished task, that is at least `threshold` similar to
+    /// `text`. Tags in `text` are left out of the comparison.
+    fn find_duplicate(&self, text: &str, threshold: f64) -> Option<Task> {
+        let text = match format!("[ ] {}", text).parse::<Task>() {
+            Ok(task) => task.text,
+            Err(_) => text.to_string(),
+        };
+        self.unfinished_tasks()
+            .into_iter()
+            .find(|task| is_duplicate(&task.text, &text, threshold))
+    }
+
+    /// Removes tasks that are duplicates of an earlier task with the same
+    /// status. The earliest task keeps its position. Returns the removed tasks.
+    fn dedupe(&mut self, threshold: f64) -> Vec<Task> {
+        let mut kept = TaskList {
+            markers: self.markers.clone(),
+            ..TaskList::new()
+        };
+        let mut removed = vec![];
+        for task in self.tasks.iter() {
+            let is_duplicate = kept.tasks.iter().any(|kept_task| {
+                kept_task.status == task.status
+                    && is_duplicate(&kept_task.text, &task.text, threshold)
+            });
+            if is_duplicate {
+                removed.push(task.to_owned());
+            } else {
+                kept.add(task.to_owned());
+            }
+        }
+        *self = kept;
+        removed
+    }
+
+    /// The tasks as displayed to the user, numbered and grouped by status.
+    /// The sections are separated by blank lines, and a blank line always
+    /// separates the open from the closed tasks.
+    fn listing(&self, show_dates: bool) -> String {
+        let show = |task: &Task| {
+            let mut markers = String::new();
+            if !task.notes.is_empty() {
+                markers += NOTES_MARKER;
+            }
+            if !task.status.is_closed() && !self.blockers(task).is_empty() {
+                markers += BLOCKED_MARKER;
+            }
+            match show_dates {
+                true => format!("{}{}", task.display_with(&self.markers), markers),
+                false => format!(
+                    "{}{}",
+                    task.without_dates().display_with(&self.markers),
+                    markers
+                ),
+            }
+        };
+        let mut listing = String::new();
+        let mut previous_section: Option<Section> = None;
+        for (index, position) in self.display_order().into_iter().enumerate() {
+            let task = &self.tasks[position];
+            let section = self.section(task);
+            if previous_section.map_or(section.closed, |previous| previous != section) {
+                listing += "\n";
+            }
+            previous_section = Some(section);
+            listing += &format!("{} {}\n", index + 1, show(task));
+        }
+        if previous_section.is_none_or(|section| !section.closed) {
+            listing += "\n";
+        }
+        listing
+    }
+
+    /// A task as shown in messages: without dates, with the list's markers
+    fn show(&self, task: &Task) -> String {
+        task.without_dates().display_with(&self.markers).to_string()
+    }
+
     fn to_string_unordered(&self) -> String {
         self.tasks
             .iter()
-            .map(|x| x.to_string())
+            .map(|task| {
+                let mut lines = vec![task.display_with(&self.markers).to_string()];
+                for note in task.notes.iter() {
+                    lines.push(format!("{}{}", NOTE_INDENT, note).trim_end().to_string());
+                }
+                lines.join("\n")
+            })
             .collect::<Vec<String>>()
             .join("\n")
             + "\n"
     }
 
-    fn check(&mut self, task_index: usize) -> Result<Task, Error> {
-        if task_index == 0 || task_index - 1 > self.tasks.len() {
-            Err(Error::IndexOutOfBounds)
-        } else {
-            let mut tasks = self.unfinished_tasks();
-            tasks.append(&mut self.finished_tasks());
-            let task_to_check = tasks.get(task_index - 1).unwrap();
-            let task = self.tasks.iter_mut().find_map(|task| {
-                if task == task_to_check {
-                    Some(task.check())
-                } else {
-                    None
+    /// Open tasks, that `task` comes `after:`
+    fn blockers(&self, task: &Task) -> Vec<&Task> {
+        task.after
+            .iter()
+            .filter_map(|id| self.tasks.iter().find(|other| other.id == Some(*id)))
+            .filter(|prerequisite| !prerequisite.status.is_closed())
+            .collect()
+    }
+
+    /// The status of a task as displayed. Open tasks, that wait for other
+    /// tasks, are displayed as blocked.
+    fn displayed_status<'a>(&self, task: &'a Task) -> &'a Status {
+        match (&task.status, self.blockers(task).is_empty()) {
+            (Status::Unfinished, false) => &Status::Blocked,
+            (status, _) => status,
+        }
+    }
+
+    fn section(&self, task: &Task) -> Section {
+        let status = self.displayed_status(task);
+        Section {
+            rank: status.rank(),
+            closed: status.is_closed(),
+        }
+    }
+
+    /// Positions of the tasks in `self.tasks`, in the order they are displayed
+    fn display_order(&self) -> Vec<usize> {
+        let mut positions: Vec<usize> = (0..self.tasks.len()).collect();
+        positions.sort_by_key(|&position| self.section(&self.tasks[position]));
+        positions
+    }
+
+    /// Sums up the estimates per status, in the order of the listing. Blocked
+    /// tasks count as blocked, even if they're open.
+    fn estimates_by_status(&self) -> Vec<(String, Total)> {
+        let mut totals: Vec<(String, Total)> = vec![];
+        for position in self.display_order() {
+            let task = &self.tasks[position];
+            let estimate = match task.estimate {
+                Some(estimate) => estimate,
+                None => continue,
+            };
+            let name = self.displayed_status(task).name();
+            match totals.iter_mut().find(|(status, _)| *status == name) {
+                Some((_, total)) => *total += estimate,
+                None => {
+                    let mut total = Total::default();
+                    total += estimate;
+                    totals.push((name, total));
                 }
-            });
-            Ok(task.unwrap())
+            }
+        }
+        totals
+    }
+
+    /// Sums up the estimates of the tasks, that aren't closed, per tag
+    fn estimates_by_tag(&self) -> BTreeMap<String, Total> {
+        let mut totals: BTreeMap<String, Total> = BTreeMap::new();
+        for task in self.tasks.iter().filter(|task| !task.status.is_closed()) {
+            let estimate = match task.estimate {
+                Some(estimate) => estimate,
+                None => continue,
+            };
+            let keys = match task.tags().as_slice() {
+                [] => vec!["(no tag)".to_string()],
+                tags => tags.iter().map(|tag| format!("+{}", tag)).collect(),
+            };
+            for key in keys {
+                *totals.entry(key).or_default() += estimate;
+            }
         }
+        totals
+    }
+
+    /// Positions of the open and started tasks, that fit into `capacity`.
+    /// Tasks are picked by priority, then by due date, then in the order of
+    /// the listing. Blocked tasks can't be worked on, so they're left out.
+    fn plan(&self, capacity: Estimate) -> Vec<usize> {
+        let mut candidates: Vec<usize> = self
+            .display_order()
+            .into_iter()
+            .filter(|&position| {
+                matches!(
+                    self.displayed_status(&self.tasks[position]),
+                    Status::Unfinished | Status::InProgress
+                )
+            })
+            .collect();
+        candidates.sort_by_key(|&position| {
+            let task = &self.tasks[position];
+            (
+                task.priority.is_none(),
+                task.priority,
+                task.due.is_none(),
+                task.due,
+            )
+        });
+        let estimates: Vec<Option<Estimate>> = candidates
+            .iter()
+            .map(|&position| self.tasks[position].estimate)
+            .collect();
+        estimate::fill(capacity, &estimates)
+            .into_iter()
+            .map(|index| candidates[index])
+            .collect()
+    }
+
+    /// The task at `position` and the tasks it comes `after:`, drawn as a
+    /// tree. Tasks, that depend on each other in a cycle, are only drawn once.
+    fn dependency_tree(&self, position: usize) -> String {
+        let task = &self.tasks[position];
+        let mut tree = format!("{}\n", task.without_dates().display_with(&self.markers));
+        self.draw_dependencies(task, &mut vec![task.id], "", &mut tree);
+        tree
+    }
+
+    fn draw_dependencies(
+        &self,
+        task: &Task,
+        path: &mut Vec<Option<u32>>,
+        indent: &str,
+        tree: &mut String,
+    ) {
+        for (number, id) in task.after.iter().enumerate() {
+            let (branch, next_indent) = match number + 1 == task.after.len() {
+                true => ("└── ", "    "),
+                false => ("├── ", "│   "),
+            };
+            match self.tasks.iter().find(|other| other.id == Some(*id)) {
+                None => *tree += &format!("{}{}id:{} (not on the list)\n", indent, branch, id),
+                Some(_) if path.contains(&Some(*id)) => {
+                    *tree += &format!("{}{}id:{} (cycle)\n", indent, branch, id)
+                }
+                Some(prerequisite) => {
+                    *tree += &format!(
+                        "{}{}{}\n",
+                        indent,
+                        branch,
+                        prerequisite.without_dates().display_with(&self.markers)
+                    );
+                    path.push(Some(*id));
+                    let indent = format!("{}{}", indent, next_indent);
+                    self.draw_dependencies(prerequisite, path, &indent, tree);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// Index as displayed of the task tagged `id:<id>`
+    fn index_of(&self, id: u32) -> Option<usize> {
+        self.display_order()
+            .into_iter()
+            .position(|position| self.tasks[position].id == Some(id))
+            .map(|index| index + 1)
+    }
+
+    /// Translates an index as displayed into a position in `self.tasks`
+    fn position(&self, task_index: usize) -> Result<usize, Error> {
+        task_index
+            .checked_sub(1)
+            .and_then(|index| self.display_order().get(index).copied())
+            .ok_or(Error::IndexOutOfBounds)
+    }
+
+    /// Finishes a task, unless it's blocked. Finishing a recurring task
+    /// appends its next occurrence.
+    fn check(&mut self, task_index: usize, on: NaiveDate) -> Result<Task, Error> {
+        let position = self.position(task_index)?;
+        let blockers = self.blockers(&self.tasks[position]);
+        if !blockers.is_empty() && !self.tasks[position].is_finished() {
+            return Err(Error::Blocked(blockers.into_iter().cloned().collect()));
+        }
+        let was_finished = self.tasks[position].is_finished();
+        let checked_task = self.tasks[position].check(on);
+        if let (false, Some(next)) = (was_finished, checked_task.next_occurrence(on)) {
+            self.add(next);
+        }
+        Ok(checked_task)
+    }
+
+    /// The `id:` of a task, assigning the next free one if it has none yet
+    fn identify(&mut self, task_index: usize) -> Result<u32, Error> {
+        let position = self.position(task_index)?;
+        if let Some(id) = self.tasks[position].id {
+            return Ok(id);
+        }
+        let id = self
+            .tasks
+            .iter()
+            .filter_map(|task| task.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.tasks[position].id = Some(id);
+        Ok(id)
+    }
+
+    fn uncheck(&mut self, task_index: usize) -> Result<Task, Error> {
+        let position = self.position(task_index)?;
+        Ok(self.tasks[position].uncheck())
+    }
+
+    /// Gives a task any status but finished, which is left to `check`. A
+    /// custom status, that counts as done, completes the task `on` that day.
+    fn set_status(
+        &mut self,
+        task_index: usize,
+        status: Status,
+        on: NaiveDate,
+    ) -> Result<Task, Error> {
+        let position = self.position(task_index)?;
+        Ok(self.tasks[position].set_status(status, on))
+    }
+
+    /// Replaces the text of a task, keeping its status, dates and tags
+    fn edit(&mut self, task_index: usize, text: &str) -> Result<Task, Error> {
+        let position = self.position(task_index)?;
+        self.tasks[position].text = text.to_string();
+        Ok(self.tasks[position].clone())
+    }
+
+    /// Replaces the notes of a task
+    fn annotate(&mut self, task_index: usize, notes: Vec<String>) -> Result<Task, Error> {
+        let position = self.position(task_index)?;
+        self.tasks[position].notes = notes;
+        Ok(self.tasks[position].clone())
     }
 
     fn remove(&mut self, task_index: usize) -> Result<Task, Error> {
-        if task_index == 0 || task_index - 1 > self.tasks.len() {
-            Err(Error::IndexOutOfBounds)
-        } else {
-            let mut tasks = self.unfinished_tasks();
-            tasks.append(&mut self.finished_tasks());
-            let task_to_remove = tasks.get(task_index - 1).unwrap();
-            self.tasks = self
-                .tasks
-                .iter()
-                .filter(|task| &task_to_remove != task)
-                .map(|task| task.to_owned())
-                .collect();
-
-            Ok(task_to_remove.to_owned())
+        let position = self.position(task_index)?;
+        Ok(self.tasks.remove(position))
+    }
+
+    /// Moves a task to a new displayed index. Tasks only move among tasks with
+    /// the same status, so `new_index` is clamped to that range. The relative
+    /// order of all other tasks stays the same.
+    fn move_task(&mut self, task_index: usize, new_index: usize) -> Result<Task, Error> {
+        let position = self.position(task_index)?;
+        let section = self.section(&self.tasks[position]);
+        let display_order = self.display_order();
+        let group_offset = display_order
+            .iter()
+            .position(|&position| self.section(&self.tasks[position]) == section)
+            .unwrap();
+        let mut group: Vec<usize> = display_order
+            .into_iter()
+            .filter(|&position| self.section(&self.tasks[position]) == section)
+            .collect();
+        let slots = group.clone();
+
+        group.retain(|&other| other != position);
+        let target = new_index
+            .saturating_sub(1)
+            .saturating_sub(group_offset)
+            .min(group.len());
+        group.insert(target, position);
+
+        let mut reordered = self.tasks.clone();
+        for (slot, position) in slots.iter().zip(group.iter()) {
+            reordered[*slot] = self.tasks[*position].clone();
         }
+        self.tasks = reordered;
+        Ok(self.tasks[slots[target]].clone())
     }
 }
 
 impl fmt::Display for TaskList {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut counter = 1;
-        for unfinished_task in self.unfinished_tasks().iter() {
-            writeln!(f, "{} {}", counter, unfinished_task)?;
-            counter += 1;
-        }
-        writeln!(f)?;
-        for finished_task in self.finished_tasks().iter() {
-            writeln!(f, "{} {}", counter, finished_task)?;
-            counter += 1;
-        }
-        Ok(()) // TODO: can I remove the loops in favor of iterators
+        write!(f, "{}", self.listing(false))
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::*;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, 10, 14).unwrap()
+    }
+
+    #[test]
+    fn test_listing_shows_dates_only_on_request() {
+        let tasks = "[ ] 2022-10-02 Task 1\n[x] 2022-10-14 2022-10-01 Task 2"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!("1 [ ] Task 1\n\n2 [x] Task 2\n", tasks.to_string());
+        assert_eq!(
+            "1 [ ] 2022-10-02 Task 1\n\n2 [x] 2022-10-14 2022-10-01 Task 2\n",
+            tasks.listing(true)
+        );
+    }
     #[test]
     fn test_parse_and_display_a_list_of_tasks() {
         let mut tasks = TaskList::new();
         let task1 = Task {
             text: "Do this task first".to_string(),
             status: Status::Finished,
+            ..Default::default()
         };
         let task2 = Task {
             text: "Then do this task".to_string(),
             status: Status::Unfinished,
+            ..Default::default()
         };
         let task3 = Task {
             text: "Finally do this task".to_string(),
             status: Status::Unfinished,
+            ..Default::default()
         };
         tasks.add(task1);
         tasks.add(task2);
@@ -293,10 +704,12 @@ mod test {
         let task1 = Task {
             text: "Do this task first".to_string(),
             status: Status::Finished,
+            ..Default::default()
         };
         let task2 = Task {
             text: "Then do this task".to_string(),
             status: Status::Unfinished,
+            ..Default::default()
         };
         tasks.add(task1.clone());
         tasks.add(task2.clone());
@@ -310,10 +723,12 @@ mod test {
         let task1 = Task {
             text: "Do this task first".to_string(),
             status: Status::Finished,
+            ..Default::default()
         };
         let task2 = Task {
             text: "Then do this task".to_string(),
             status: Status::Unfinished,
+            ..Default::default()
         };
         tasks.add(task1.clone());
         tasks.add(task2.clone());
@@ -333,6 +748,20 @@ mod test {
         assert_eq!(expected_tasks, tasks);
     }
 
+    #[test]
+    fn test_parse_refuses_conflicts_and_invalid_lines() {
+        let conflicted = "[ ] Task 1\n<<<<<<< ours\n[ ] Task 2\n=======\n>>>>>>> theirs\n";
+
+        assert_eq!(
+            Err(Error::UnresolvedConflict),
+            TaskList::parse_with(conflicted, &Markers::default())
+        );
+        assert_eq!(
+            Err(Error::InvalidItem(2)),
+            TaskList::parse_with("[ ] Task 1\nTask 2\n", &Markers::default())
+        );
+    }
+
     #[test]
     fn test_to_string_unordered() {
         let tasks = "[ ] Task 1\n[x] Task 2".parse::<TaskList>().unwrap();
@@ -343,28 +772,276 @@ mod test {
     fn test_check_marks_task_and_returns_task() {
         let mut tasks = "[ ] Task 1\n[x] Task 2".parse::<TaskList>().unwrap();
 
-        let task = tasks.check(1).unwrap();
-        assert_eq!("[x] Task 1\n[x] Task 2\n", tasks.to_string_unordered());
+        let task = tasks.check(1, day()).unwrap();
+        assert_eq!(
+            "[x] 2022-10-14 Task 1\n[x] Task 2\n",
+            tasks.to_string_unordered()
+        );
         assert_eq!(
             Task {
                 text: "Task 1".to_string(),
-                status: Status::Finished
+                status: Status::Finished,
+                completed: Some(day()),
+                ..Default::default()
             },
             task
         );
     }
-    // TODO: make unmarking also possible
+
+    #[test]
+    fn test_check_appends_next_occurrence() {
+        let mut tasks = "[ ] Release due:2022-10-12 rec:+2w\n[ ] Task 2"
+            .parse::<TaskList>()
+            .unwrap();
+
+        tasks.check(1, day()).unwrap();
+        tasks.check(3, day()).unwrap();
+
+        assert_eq!(
+            "[x] 2022-10-14 Release due:2022-10-12 rec:+2w\n[ ] Task 2\n\
+             [ ] 2022-10-14 Release due:2022-10-26 rec:+2w\n",
+            tasks.to_string_unordered()
+        );
+    }
+
+    #[test]
+    fn test_identify_assigns_next_free_id() {
+        let mut tasks = "[ ] Task 1\n[ ] Task 2 id:4\n[ ] Task 3"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(Ok(5), tasks.identify(3));
+        assert_eq!(Ok(4), tasks.identify(2));
+        assert_eq!(Ok(5), tasks.identify(3));
+        assert_eq!(
+            "[ ] Task 1\n[ ] Task 2 id:4\n[ ] Task 3 id:5\n",
+            tasks.to_string_unordered()
+        );
+    }
+
+    #[test]
+    fn test_notes_are_indented_lines_below_their_task() {
+        let content = "[ ] Crash on empty input\n    1. Run `todo add \"\"`\n\n\
+                       \t2. See it panic\n\n[x] Task 2\n";
+        let mut tasks = content.parse::<TaskList>().unwrap();
+
+        assert_eq!(2, tasks.tasks.len());
+        assert_eq!(
+            vec!["1. Run `todo add \"\"`", "", "2. See it panic"],
+            tasks.tasks[0].notes
+        );
+        assert_eq!(
+            "1 [ ] Crash on empty input (notes)\n\n2 [x] Task 2\n",
+            tasks.listing(false)
+        );
+
+        assert_eq!(
+            "[ ] Crash on empty input\n    1. Run `todo add \"\"`\n\n    2. See it panic\n[x] Task 2\n",
+            tasks.to_string_unordered()
+        );
+
+        tasks
+            .annotate(2, vec!["Done in a hurry".to_string()])
+            .unwrap();
+        tasks.annotate(1, vec![]).unwrap();
+        assert_eq!(
+            "[ ] Crash on empty input\n[x] Task 2\n    Done in a hurry\n",
+            tasks.to_string_unordered()
+        );
+    }
+
+    #[test]
+    fn test_index_of_id_as_displayed() {
+        let tasks = "[x] Task 1 id:1\n[ ] Task 2 id:2\n[ ] Task 3"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(Some(3), tasks.index_of(1));
+        assert_eq!(Some(1), tasks.index_of(2));
+        assert_eq!(None, tasks.index_of(3));
+    }
+
+    #[test]
+    fn test_blocked_tasks_are_listed_separately() {
+        let mut tasks = "[ ] Publish after:2\n[ ] Tag version id:2 after:1\n\
+                         [ ] Run tests id:1\n[x] Write changelog"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(
+            "1 [ ] Run tests id:1\n\n\
+             2 [ ] Publish after:2 (blocked)\n3 [ ] Tag version after:1 id:2 (blocked)\n\n\
+             4 [x] Write changelog\n",
+            tasks.listing(false)
+        );
+        assert_eq!(
+            Err(Error::Blocked(vec!["[ ] Tag version after:1 id:2"
+                .parse()
+                .unwrap()])),
+            tasks.check(2, day())
+        );
+
+        tasks.check(1, day()).unwrap();
+        assert_eq!(
+            "1 [ ] Tag version after:1 id:2\n\n\
+             2 [ ] Publish after:2 (blocked)\n\n\
+             3 [x] Run tests id:1\n4 [x] Write changelog\n",
+            tasks.listing(false)
+        );
+    }
+
+    #[test]
+    fn test_listing_groups_tasks_by_status() {
+        let mut tasks = "[-] Rewrite in Go\n[?] Feedback\n[ ] Docs\n[x] Tests\n\
+                         [!] Deploy\n[*] Unknown\n[~] Release\n[ ] Publish after:1\n[ ] Ship id:1"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(
+            "1 [~] Release\n\n\
+             2 [ ] Docs\n3 [ ] Ship id:1\n\n\
+             4 [!] Deploy\n5 [ ] Publish after:1 (blocked)\n\n\
+             6 [?] Feedback\n\n\
+             7 [*] Unknown\n\n\
+             8 [x] Tests\n\n\
+             9 [-] Rewrite in Go\n",
+            tasks.listing(false)
+        );
+
+        tasks.set_status(3, Status::Cancelled, day()).unwrap();
+        assert_eq!(
+            "1 [~] Release\n\n2 [ ] Docs\n3 [ ] Publish after:1\n\n",
+            tasks.listing(false).split("4 [!]").next().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_only_closed_tasks_are_listed_after_a_blank_line() {
+        let tasks = "[-] Rewrite in Go".parse::<TaskList>().unwrap();
+
+        assert_eq!("\n1 [-] Rewrite in Go\n", tasks.listing(false));
+        assert_eq!("\n", TaskList::new().listing(false));
+    }
+
+    #[test]
+    fn test_estimates_by_status_and_tag() {
+        let tasks = "[ ] Invoice +acme est:2h\n[~] Review +acme +billing est:3\n\
+                     [ ] Publish after:1 est:30m\n[ ] Tag version id:1\n[x] Docs est:1h"
+            .parse::<TaskList>()
+            .unwrap();
+
+        let by_status: Vec<(String, String)> = tasks
+            .estimates_by_status()
+            .into_iter()
+            .map(|(status, total)| (status, total.to_string()))
+            .collect();
+        let by_tag: Vec<(String, String)> = tasks
+            .estimates_by_tag()
+            .into_iter()
+            .map(|(tag, total)| (tag, total.to_string()))
+            .collect();
+
+        let pair = |key: &str, total: &str| (key.to_string(), total.to_string());
+        assert_eq!(
+            vec![
+                pair("in-progress", "3 points"),
+                pair("open", "2h 00m"),
+                pair("blocked", "0h 30m"),
+                pair("done", "1h 00m"),
+            ],
+            by_status
+        );
+        assert_eq!(
+            vec![
+                pair("(no tag)", "0h 30m"),
+                pair("+acme", "3 points, 2h 00m"),
+                pair("+billing", "3 points"),
+            ],
+            by_tag
+        );
+    }
+
+    #[test]
+    fn test_plan_picks_by_priority_and_due_date() {
+        let tasks =
+            "[ ] Docs est:2h\n[ ] Fix crash pri:A est:3h\n[ ] Invoice due:2022-10-20 est:1h\n\
+                     [ ] Publish after:1 pri:A est:1h\n[ ] Tag version id:1 pri:B est:4h\n\
+                     [~] Review est:1h\n[x] Tests pri:A est:1h"
+                .parse::<TaskList>()
+                .unwrap();
+
+        let planned: Vec<&str> = tasks
+            .plan(Estimate::Minutes(360))
+            .into_iter()
+            .map(|position| tasks.tasks[position].text.as_str())
+            .collect();
+
+        assert_eq!(vec!["Fix crash", "Invoice", "Review"], planned);
+    }
+
+    #[test]
+    fn test_dependency_tree_stops_at_cycles() {
+        let tasks = "[ ] Publish id:3 after:2 after:7 after:4\n[ ] Tag version id:2 after:1\n\
+                     [x] Run tests id:1 after:3\n[ ] Write changelog id:4"
+            .parse::<TaskList>()
+            .unwrap();
+
+        assert_eq!(
+            "[ ] Publish after:2 after:7 after:4 id:3\n\
+             ├── [ ] Tag version after:1 id:2\n\
+             │   └── [x] Run tests after:3 id:1\n\
+             │       └── id:3 (cycle)\n\
+             ├── id:7 (not on the list)\n\
+             └── [ ] Write changelog id:4\n",
+            tasks.dependency_tree(0)
+        );
+    }
+
+    #[test]
+    fn test_uncheck_removes_completion_date() {
+        let mut tasks = "[ ] Task 1\n[x] 2022-10-14 2022-10-02 Task 2"
+            .parse::<TaskList>()
+            .unwrap();
+
+        let task = tasks.uncheck(2).unwrap();
+
+        assert_eq!("[ ] Task 2", task.without_dates().to_string());
+        assert_eq!(
+            "[ ] Task 1\n[ ] 2022-10-02 Task 2\n",
+            tasks.to_string_unordered()
+        );
+    }
+
+    #[test]
+    fn test_edit_keeps_status_and_tags() {
+        let mut tasks = "[x] 2022-10-14 Task 2\n[ ] Tsak 1 due:2022-10-20"
+            .parse::<TaskList>()
+            .unwrap();
+
+        tasks.edit(1, "Task 1").unwrap();
+
+        assert_eq!(
+            "[x] 2022-10-14 Task 2\n[ ] Task 1 due:2022-10-20\n",
+            tasks.to_string_unordered()
+        );
+        assert_eq!(Err(Error::IndexOutOfBounds), tasks.edit(3, "Task 3"));
+    }
 
     #[test]
     fn test_check_indexes_correctly() {
         let mut tasks = "[x] Task 2\n[ ] Task 1".parse::<TaskList>().unwrap();
 
-        let checked_task = tasks.check(1).unwrap();
-        assert_eq!("[x] Task 2\n[x] Task 1\n", tasks.to_string_unordered());
+        let checked_task = tasks.check(1, day()).unwrap();
+        assert_eq!(
+            "[x] Task 2\n[x] 2022-10-14 Task 1\n",
+            tasks.to_string_unordered()
+        );
         assert_eq!(
             Task {
                 text: "Task 1".to_string(),
-                status: Status::Finished
+                status: Status::Finished,
+                completed: Some(day()),
+                ..Default::default()
             },
             checked_task
         );
@@ -373,7 +1050,7 @@ mod test {
     #[test]
     fn test_check_returns_out_of_bounds_error() {
         let mut tasks = "[ ] Task 1\n[x] Task 2".parse::<TaskList>().unwrap();
-        let result = tasks.check(5);
+        let result = tasks.check(5, day());
         assert_eq!(Err(Error::IndexOutOfBounds), result);
     }
 
@@ -388,7 +1065,8 @@ mod test {
         assert_eq!(
             Task {
                 text: "Buy all of the books from the series".to_string(),
-                status: Status::Finished
+                status: Status::Finished,
+                ..Default::default()
             },
             task2
         );
@@ -396,11 +1074,13 @@ mod test {
             vec![
                 Task {
                     text: "Read a book from a series of books".to_string(),
-                    status: Status::Finished
+                    status: Status::Finished,
+                    ..Default::default()
                 },
                 Task {
                     text: "Read all the books from the book series".to_string(),
-                    status: Status::Unfinished
+                    status: Status::Unfinished,
+                    ..Default::default()
                 }
             ],
             tasks.tasks
@@ -410,14 +1090,16 @@ mod test {
         assert_eq!(
             Task {
                 text: "Read all the books from the book series".to_string(),
-                status: Status::Unfinished
+                status: Status::Unfinished,
+                ..Default::default()
             },
             task3
         );
         assert_eq!(
             vec![Task {
                 text: "Read a book from a series of books".to_string(),
-                status: Status::Finished
+                status: Status::Finished,
+                ..Default::default()
             }],
             tasks.tasks
         );
@@ -425,6 +1107,140 @@ mod test {
         assert_eq!(Err(Error::IndexOutOfBounds), tasks.remove(5));
     }
 
+    #[test]
+    fn test_check_and_remove_only_affect_one_of_several_equal_tasks() {
+        let mut tasks = "[ ] Task\n[ ] Task".parse::<TaskList>().unwrap();
+
+        tasks.check(2, day()).unwrap();
+        assert_eq!(
+            "[ ] Task\n[x] 2022-10-14 Task\n",
+            tasks.to_string_unordered()
+        );
+
+        tasks.remove(1).unwrap();
+        assert_eq!("[x] 2022-10-14 Task\n", tasks.to_string_unordered());
+        assert_eq!(Err(Error::IndexOutOfBounds), tasks.remove(2));
+        assert_eq!(Err(Error::IndexOutOfBounds), tasks.remove(0));
+    }
+
+    #[test]
+    fn test_move_task_keeps_order_of_other_tasks() {
+        let mut tasks = "[ ] A\n[x] Done\n[ ] B\n[ ] C\n[ ] D"
+            .parse::<TaskList>()
+            .unwrap();
+
+        let moved = tasks.move_task(4, 2).unwrap();
+
+        assert_eq!("[ ] D".parse::<Task>().unwrap(), moved);
+        assert_eq!(
+            "[ ] A\n[x] Done\n[ ] D\n[ ] B\n[ ] C\n",
+            tasks.to_string_unordered()
+        );
+
+        tasks.move_task(1, 3).unwrap();
+        assert_eq!(
+            "[ ] D\n[x] Done\n[ ] B\n[ ] A\n[ ] C\n",
+            tasks.to_string_unordered()
+        );
+    }
+
+    #[test]
+    fn test_move_task_stays_within_its_status() {
+        let mut tasks = "[x] Done 1\n[ ] A\n[x] Done 2\n[ ] B"
+            .parse::<TaskList>()
+            .unwrap();
+
+        // moving to the top or bottom clamps to the tasks with the same status
+        tasks.move_task(1, usize::MAX).unwrap();
+        assert_eq!(
+            "[x] Done 1\n[ ] B\n[x] Done 2\n[ ] A\n",
+            tasks.to_string_unordered()
+        );
+        tasks.move_task(4, 1).unwrap();
+        assert_eq!(
+            "[x] Done 2\n[ ] B\n[x] Done 1\n[ ] A\n",
+            tasks.to_string_unordered()
+        );
+        assert_eq!(Err(Error::IndexOutOfBounds), tasks.move_task(5, 1));
+    }
+
+    #[test]
+    fn test_parse_task_ref() {
+        assert_eq!(
+            TaskRef {
+                path: None,
+                index: 3
+            },
+            "3".parse::<TaskRef>().unwrap()
+        );
+        assert_eq!(
+            TaskRef {
+                path: Some("crates/core/.todo".into()),
+                index: 12
+            },
+            "crates/core/.todo:12".parse::<TaskRef>().unwrap()
+        );
+        assert!("crates/core/.todo".parse::<TaskRef>().is_err());
+        assert!(":1".parse::<TaskRef>().is_err());
+    }
+
+    #[test]
+    fn test_similarity_ignores_case_and_whitespace() {
+        assert_eq!(1.0, similarity("Refactor  code", " refactor Code"));
+        assert!(similarity("Refactor code", "Refactor codes") > 0.9);
+        assert!(similarity("Refactor code", "Drink a coffee with Greg") < 0.5);
+    }
+
+    #[test]
+    fn test_find_duplicate_only_considers_unfinished_tasks() {
+        let tasks = "[x] Buy milk\n[ ] Write tests".parse::<TaskList>().unwrap();
+
+        assert_eq!(None, tasks.find_duplicate("buy milk", 0.9));
+        assert_eq!(
+            Some("[ ] Write tests".parse::<Task>().unwrap()),
+            tasks.find_duplicate("write  tests", 0.9)
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_ignores_tags_of_the_new_task() {
+        let tasks = "[ ] Renew certificate".parse::<TaskList>().unwrap();
+
+        assert_eq!(
+            Some("[ ] Renew certificate".parse::<Task>().unwrap()),
+            tasks.find_duplicate("Renew certificate due:2026-11-01 pri:A", 0.9)
+        );
+    }
+
+    #[test]
+    fn test_texts_with_different_numbers_are_no_duplicates() {
+        let tasks = "[ ] Release v1.2.4".parse::<TaskList>().unwrap();
+
+        assert_eq!(None, tasks.find_duplicate("Release v1.2.5", 0.9));
+        assert!(tasks.find_duplicate("release v1.2.4", 0.9).is_some());
+    }
+
+    #[test]
+    fn test_dedupe_keeps_earliest_task() {
+        let mut tasks = "[ ] Task 1\n[ ] Task 2\n[x] Task 1\n[ ] task 1 \n[x] Task 1"
+            .parse::<TaskList>()
+            .unwrap();
+
+        let removed = tasks.dedupe(1.0);
+
+        assert_eq!(
+            "[ ] Task 1\n[ ] Task 2\n[x] Task 1\n",
+            tasks.to_string_unordered()
+        );
+        assert_eq!(
+            vec![
+                "[ ] task 1 ".parse::<Task>().unwrap(),
+                "[x] Task 1".parse::<Task>().unwrap()
+            ],
+            removed
+        );
+    }
+
     #[test]
     fn test_remove_takes_index_as_displayed_not_as_in_file() {
         let mut tasks = "[x] Task2\n[ ] Task1".parse::<TaskList>().unwrap();
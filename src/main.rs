@@ -1,4 +1,7 @@
 mod arg_parsing;
+mod completions;
+mod man;
+mod shell;
 
 use crate::arg_parsing::Args;
 use clap::Parser;
@@ -0,0 +1,219 @@
+use crate::arg_parsing::Args;
+use clap::{Arg, CommandFactory};
+use roff::{bold, italic, roman, Inline, Roff};
+use std::fs;
+use std::path::Path;
+
+/// `<TEXT>` for positional arguments, `-f, --force` or `--list=LIST` for
+/// options. The short and long flag are joined by `separator`.
+fn arg_usage(arg: &Arg, separator: &str) -> Vec<Inline> {
+    let value_name = arg
+        .get_value_names()
+        .and_then(|names| names.first().map(|name| name.to_string()))
+        .unwrap_or_else(|| arg.get_id().to_uppercase());
+    if arg.is_positional() {
+        return vec![roman("<"), italic(value_name), roman(">")];
+    }
+    let mut flags = vec![];
+    if let Some(short) = arg.get_short() {
+        flags.push(bold(format!("-{}", short)));
+    }
+    if let Some(long) = arg.get_long() {
+        if !flags.is_empty() {
+            flags.push(roman(separator));
+        }
+        flags.push(bold(format!("--{}", long)));
+    }
+    if arg.is_takes_value_set() {
+        flags.push(roman("="));
+        flags.push(italic(value_name));
+    }
+    flags
+}
+
+fn arg_description(arg: &Arg) -> String {
+    let mut description = arg
+        .get_long_help()
+        .or_else(|| arg.get_help())
+        .unwrap_or_default()
+        .to_string();
+    let values: Vec<String> = match arg.get_possible_values() {
+        Some(values) => values
+            .iter()
+            .map(|value| value.get_name().to_string())
+            .collect(),
+        None => arg
+            .get_value_parser()
+            .possible_values()
+            .map(|values| values.map(|value| value.get_name().to_string()).collect())
+            .unwrap_or_default(),
+    };
+    if !values.is_empty() {
+        description += &format!(" [possible values: {}]", values.join(", "));
+    }
+    if let [default] = arg.get_default_values() {
+        description += &format!(" [default: {}]", default.to_string_lossy());
+    }
+    description
+}
+
+/// Renders the man page of `command`, called `name` on the command line,
+/// e.g. `todo add`
+fn render(command: &clap::Command, name: &str, subcommands: &[(String, String)]) -> String {
+    let page_name = name.replace(' ', "-");
+    let title = page_name.to_uppercase();
+    let version = format!("todo-rs {}", env!("CARGO_PKG_VERSION"));
+    let mut page = Roff::new();
+    page.control("TH", [title.as_str(), "1", "\"\"", version.as_str()]);
+
+    page.control("SH", ["NAME"]);
+    page.text([roman(format!(
+        "{} - {}",
+        page_name,
+        command.get_about().unwrap_or_default()
+    ))]);
+
+    let arguments: Vec<&Arg> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .collect();
+    page.control("SH", ["SYNOPSIS"]);
+    let mut synopsis = vec![bold(name)];
+    for arg in arguments.iter() {
+        synopsis.push(roman(" "));
+        match arg.is_required_set() || arg.is_positional() {
+            true => synopsis.extend(arg_usage(arg, "|")),
+            false => {
+                synopsis.push(roman("["));
+                synopsis.extend(arg_usage(arg, "|"));
+                synopsis.push(roman("]"));
+            }
+        }
+    }
+    if !subcommands.is_empty() {
+        synopsis.push(roman(" <"));
+        synopsis.push(italic("SUBCOMMAND"));
+        synopsis.push(roman(">"));
+    }
+    page.text(synopsis);
+
+    page.control("SH", ["DESCRIPTION"]);
+    page.text([roman(
+        command
+            .get_long_about()
+            .or_else(|| command.get_about())
+            .unwrap_or_default(),
+    )]);
+
+    if !arguments.is_empty() {
+        page.control("SH", ["OPTIONS"]);
+        for arg in arguments {
+            page.control("TP", []);
+            page.text(arg_usage(arg, ", "));
+            page.text([roman(arg_description(arg))]);
+        }
+    }
+
+    if !subcommands.is_empty() {
+        page.control("SH", ["SUBCOMMANDS"]);
+        for (page_name, about) in subcommands {
+            page.control("TP", []);
+            page.text([bold(page_name), roman("(1)")]);
+            page.text([roman(about)]);
+        }
+    }
+
+    page.control("SH", ["SEE ALSO"]);
+    match subcommands.is_empty() {
+        true => page.text([bold("todo"), roman("(1)")]),
+        false => page.text([roman("The man pages of the subcommands listed above.")]),
+    };
+    if let Some(author) = command.get_author() {
+        page.control("SH", ["AUTHORS"]);
+        page.text([roman(author)]);
+    }
+    page.render()
+}
+
+/// Renders `todo.1` and a page for every subcommand, e.g. `todo-add.1`.
+/// Returns the file names together with the pages.
+pub fn pages() -> Vec<(String, String)> {
+    let mut command = Args::command();
+    // propagates the global `--list` and adds `--help` to every subcommand
+    command.build();
+    let subcommands: Vec<&clap::Command> = command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set() && subcommand.get_name() != "help")
+        .collect();
+    let summaries: Vec<(String, String)> = subcommands
+        .iter()
+        .map(|subcommand| {
+            (
+                format!("todo-{}", subcommand.get_name()),
+                subcommand.get_about().unwrap_or_default().to_string(),
+            )
+        })
+        .collect();
+
+    let mut pages = vec![("todo.1".to_string(), render(&command, "todo", &summaries))];
+    for subcommand in subcommands {
+        let name = format!("todo {}", subcommand.get_name());
+        pages.push((
+            format!("todo-{}.1", subcommand.get_name()),
+            render(subcommand, &name, &[]),
+        ));
+    }
+    pages
+}
+
+/// Writes all man pages to `out_dir`
+pub fn generate(out_dir: &Path, writer: &mut impl std::io::Write) {
+    fs::create_dir_all(out_dir).expect("Directory could not be created");
+    for (file_name, page) in pages() {
+        let path = out_dir.join(file_name);
+        fs::write(&path, page).expect("File could not be created");
+        writeln!(writer, "Wrote: {}", path.display()).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_a_page_for_every_subcommand() {
+        let pages = pages();
+        let names: Vec<&str> = pages.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!("todo.1", names[0]);
+        assert!(names.contains(&"todo-add.1"));
+        assert!(names.contains(&"todo-sync-code.1"));
+        assert!(!names.contains(&"todo-complete-items.1"));
+        assert!(!names.contains(&"todo-help.1"));
+    }
+
+    #[test]
+    fn test_page_uses_doc_comments() {
+        let pages = pages();
+        let (_, page) = pages.iter().find(|(name, _)| name == "todo-add.1").unwrap();
+
+        assert!(page.contains(".TH TODO-ADD 1 \"\" \"todo-rs "));
+        assert!(page.contains("todo\\-add \\- Add an item to the todo list\n"));
+        assert!(page.contains(
+            "\\fB\\-f\\fR, \\fB\\-\\-force\\fR\nAdd the item even if a similar one is already on the list\n"
+        ));
+        assert!(page.contains("\\fB\\-\\-list\\fR=\\fILIST\\fR\n"));
+        assert!(page.contains("<\\fITEXT\\fR>\nText of the new item\n"));
+    }
+
+    #[test]
+    fn test_page_lists_possible_values() {
+        let pages = pages();
+        let (_, page) = pages
+            .iter()
+            .find(|(name, _)| name == "todo-completions.1")
+            .unwrap();
+
+        assert!(page.contains("[possible values: bash, elvish, fish, powershell, zsh]"));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::status::Markers;
+use crate::{Error, Task, TaskList};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// Identity of a task across the versions of a list: its `id:`, or else its
+/// creation date and text. Equal tasks are told apart by their order.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub(crate) enum Identity {
+    Id(u32),
+    Line(Option<NaiveDate>, String, usize),
+}
+
+pub(crate) fn identities(tasks: &[Task]) -> Vec<Identity> {
+    let mut seen: HashMap<(Option<NaiveDate>, String), usize> = HashMap::new();
+    tasks
+        .iter()
+        .map(|task| match task.id {
+            Some(id) => Identity::Id(id),
+            None => {
+                let count = seen.entry((task.created, task.text.clone())).or_default();
+                *count += 1;
+                Identity::Line(task.created, task.text.clone(), *count)
+            }
+        })
+        .collect()
+}
+
+/// The side, that changed the value, wins. `None` if both changed it differently.
+fn merge_value<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    match (ours == base, theirs == base, ours == theirs) {
+        (_, true, _) | (_, _, true) => Some(ours.clone()),
+        (true, false, false) => Some(theirs.clone()),
+        (false, false, false) => None,
+    }
+}
+
+/// Merges the changes both sides made to a task. The status, completion
+/// date and commit change together, every other part on its own.
+fn merge_task(base: &Task, ours: &Task, theirs: &Task) -> Option<Task> {
+    let completion = |task: &Task| (task.status.clone(), task.completed, task.commit.clone());
+    let (status, completed, commit) =
+        merge_value(&completion(base), &completion(ours), &completion(theirs))?;
+    Some(Task {
+        text: merge_value(&base.text, &ours.text, &theirs.text)?,
+        status,
+        source: merge_value(&base.source, &ours.source, &theirs.source)?,
+        created: merge_value(&base.created, &ours.created, &theirs.created)?,
+        completed,
+        due: merge_value(&base.due, &ours.due, &theirs.due)?,
+        priority: merge_value(&base.priority, &ours.priority, &theirs.priority)?,
+        recurrence: merge_value(&base.recurrence, &ours.recurrence, &theirs.recurrence)?,
+        estimate: merge_value(&base.estimate, &ours.estimate, &theirs.estimate)?,
+        after: merge_value(&base.after, &ours.after, &theirs.after)?,
+        id: merge_value(&base.id, &ours.id, &theirs.id)?,
+        commit,
+        notes: merge_value(&base.notes, &ours.notes, &theirs.notes)?,
+    })
+}
+
+enum Merged {
+    Task(Task),
+    /// Our and their version of a task. `None` if that side removed it.
+    Conflict(Option<Task>, Option<Task>),
+}
+
+fn lines(task: &Option<Task>, markers: &Markers) -> String {
+    match task {
+        Some(task) => TaskList {
+            tasks: vec![task.clone()],
+            markers: markers.clone(),
+        }
+        .to_string_unordered(),
+        None => String::new(),
+    }
+}
+
+/// Three-way merge of the contents of a list, as needed by git. Tasks added
+/// on either side are kept, and a change on one side wins over the
+/// unchanged task on the other. Tasks, that were changed differently on both
+/// sides, are put between conflict markers. Returns the merged list and the
+/// number of conflicts.
+pub fn merge(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    markers: &Markers,
+) -> Result<(String, usize), Error> {
+    let parse = |content: &str| TaskList::parse_with(content, markers).map(|list| list.tasks);
+    let (base, ours, theirs) = (parse(base)?, parse(ours)?, parse(theirs)?);
+    let base: HashMap<Identity, Task> = identities(&base).into_iter().zip(base).collect();
+    let our_identities = identities(&ours);
+    let their_identities = identities(&theirs);
+    let their_tasks: HashMap<&Identity, &Task> = their_identities.iter().zip(&theirs).collect();
+
+    let mut merged: Vec<(Identity, Merged)> = vec![];
+    for (identity, ours) in our_identities.iter().zip(ours.iter()) {
+        let entry = match (base.get(identity), their_tasks.get(identity)) {
+            (Some(base), Some(theirs)) => match merge_task(base, ours, theirs) {
+                Some(task) => Merged::Task(task),
+                None => Merged::Conflict(Some(ours.clone()), Some((*theirs).clone())),
+            },
+            (Some(base), None) if base == ours => continue,
+            (Some(_), None) => Merged::Conflict(Some(ours.clone()), None),
+            (None, Some(theirs)) if *theirs != ours => {
+                Merged::Conflict(Some(ours.clone()), Some((*theirs).clone()))
+            }
+            (None, _) => Merged::Task(ours.clone()),
+        };
+        merged.push((identity.clone(), entry));
+    }
+    for (index, (identity, theirs)) in their_identities.iter().zip(theirs.iter()).enumerate() {
+        if our_identities.contains(identity) {
+            continue;
+        }
+        let entry = match base.get(identity) {
+            Some(base) if base == theirs => continue,
+            Some(_) => Merged::Conflict(None, Some(theirs.clone())),
+            None => Merged::Task(theirs.clone()),
+        };
+        // keep their task behind the task it followed on their side
+        let position = their_identities[..index]
+            .iter()
+            .rev()
+            .find_map(|previous| merged.iter().position(|(identity, _)| identity == previous))
+            .map_or(0, |position| position + 1);
+        merged.insert(position, (identity.clone(), entry));
+    }
+
+    let mut content = String::new();
+    let mut conflicts = 0;
+    for (_, entry) in merged {
+        match entry {
+            Merged::Task(task) => content += &lines(&Some(task), markers),
+            Merged::Conflict(ours, theirs) => {
+                content += &format!(
+                    "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+                    lines(&ours, markers),
+                    lines(&theirs, markers)
+                );
+                conflicts += 1;
+            }
+        }
+    }
+    Ok((content, conflicts))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_additions_on_both_sides_are_kept() {
+        let base = "[ ] Task 1\n[ ] Task 2\n";
+        let ours = "[ ] Task 1\n[ ] Ours\n[ ] Task 2\n";
+        let theirs = "[ ] Theirs\n[ ] Task 1\n[ ] Task 2\n[ ] Theirs too\n";
+
+        assert_eq!(
+            (
+                "[ ] Theirs\n[ ] Task 1\n[ ] Ours\n[ ] Task 2\n[ ] Theirs too\n".to_string(),
+                0
+            ),
+            merge(base, ours, theirs, &Markers::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_changes_win_over_unchanged_tasks() {
+        let base = "[ ] Task 1 id:1\n[ ] Task 2\n[ ] Task 3\n";
+        let ours = "[x] 2022-10-14 Task 1 id:1\n[ ] Task 2\n";
+        let theirs = "[ ] Task 1 renamed due:2022-10-20 id:1\n[ ] Task 2\n[ ] Task 3\n";
+
+        assert_eq!(
+            (
+                "[x] 2022-10-14 Task 1 renamed due:2022-10-20 id:1\n[ ] Task 2\n".to_string(),
+                0
+            ),
+            merge(base, ours, theirs, &Markers::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_conflicting_changes_are_marked() {
+        let base = "[ ] Task 1 id:1\n[ ] Task 2\n";
+        let ours = "[ ] Our task id:1\n[x] 2022-10-14 Task 2\n";
+        let theirs = "[ ] Their task id:1\n";
+
+        assert_eq!(
+            (
+                "<<<<<<< ours\n[ ] Our task id:1\n=======\n[ ] Their task id:1\n>>>>>>> theirs\n\
+                 <<<<<<< ours\n[x] 2022-10-14 Task 2\n=======\n>>>>>>> theirs\n"
+                    .to_string(),
+                2
+            ),
+            merge(base, ours, theirs, &Markers::default()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::ParseError;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Interval {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+    /// Every month on the given day, e.g. `monthly-on-1st`
+    MonthDay(u32),
+}
+
+/// When a task comes back after it was finished, stored as `rec:<rule>`.
+/// Relaxed recurrences count from the day the task was finished, strict ones
+/// (`rec:+2w`) from the due date, so that finishing late doesn't shift them.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Recurrence {
+    pub interval: Interval,
+    pub strict: bool,
+}
+
+fn ordinal(day: u32) -> String {
+    let suffix = match (day % 10, day % 100) {
+        (1, 11) | (2, 12) | (3, 13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", day, suffix)
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.strict {
+            write!(f, "+")?;
+        }
+        match self.interval {
+            Interval::Days(1) => write!(f, "daily"),
+            Interval::Weeks(1) => write!(f, "weekly"),
+            Interval::Months(1) => write!(f, "monthly"),
+            Interval::Years(1) => write!(f, "yearly"),
+            Interval::Days(days) => write!(f, "{}d", days),
+            Interval::Weeks(weeks) => write!(f, "{}w", weeks),
+            Interval::Months(months) => write!(f, "{}m", months),
+            Interval::Years(years) => write!(f, "{}y", years),
+            Interval::MonthDay(day) => write!(f, "monthly-on-{}", ordinal(day)),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        let (strict, rule) = match input.strip_prefix('+') {
+            Some(rule) => (true, rule),
+            None => (false, input),
+        };
+        let interval = match rule {
+            "daily" => Interval::Days(1),
+            "weekly" => Interval::Weeks(1),
+            "monthly" => Interval::Months(1),
+            "yearly" => Interval::Years(1),
+            _ => match rule.strip_prefix("monthly-on-") {
+                Some(day) => {
+                    let day: u32 = day
+                        .trim_end_matches(char::is_alphabetic)
+                        .parse()
+                        .map_err(|_| ParseError)?;
+                    if !(1..=31).contains(&day) || rule != format!("monthly-on-{}", ordinal(day)) {
+                        return Err(ParseError);
+                    }
+                    Interval::MonthDay(day)
+                }
+                None => {
+                    let unit = rule.chars().last().ok_or(ParseError)?;
+                    let count: u32 = rule[..rule.len() - unit.len_utf8()]
+                        .parse()
+                        .map_err(|_| ParseError)?;
+                    match (unit, count) {
+                        (_, 0) => return Err(ParseError),
+                        ('d', count) => Interval::Days(count),
+                        ('w', count) => Interval::Weeks(count),
+                        ('m', count) => Interval::Months(count),
+                        ('y', count) => Interval::Years(count),
+                        _ => return Err(ParseError),
+                    }
+                }
+            },
+        };
+        Ok(Recurrence { interval, strict })
+    }
+}
+
+/// The given day of the month after `date`, or the last day of the month if
+/// that month is too short
+fn day_of_next_month(date: NaiveDate, day: u32) -> Option<NaiveDate> {
+    let next_month = date.with_day(1)?.checked_add_months(Months::new(1))?;
+    (0..4).find_map(|shorter| next_month.with_day(day - shorter))
+}
+
+impl Recurrence {
+    /// Next due date of a task that was due on `due` and finished on `completed`.
+    /// `None` if the date is out of the range of dates, e.g. for `rec:999999999d`.
+    pub fn next(&self, due: Option<NaiveDate>, completed: NaiveDate) -> Option<NaiveDate> {
+        let from = match self.strict {
+            true => due.unwrap_or(completed),
+            false => completed,
+        };
+        match self.interval {
+            Interval::Days(days) => from.checked_add_days(Days::new(days as u64)),
+            Interval::Weeks(weeks) => from.checked_add_days(Days::new(7 * weeks as u64)),
+            Interval::Months(months) => from.checked_add_months(Months::new(months)),
+            Interval::Years(years) => from.checked_add_months(Months::new(years.checked_mul(12)?)),
+            Interval::MonthDay(day) => {
+                let last_month = from.with_day(1)?.checked_sub_days(Days::new(1))?;
+                match day_of_next_month(last_month, day)? {
+                    this_month if this_month > from => Some(this_month),
+                    _ => day_of_next_month(from, day),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for rule in [
+            "daily",
+            "weekly",
+            "monthly",
+            "yearly",
+            "+2w",
+            "3d",
+            "+6m",
+            "2y",
+            "monthly-on-1st",
+            "+monthly-on-22nd",
+            "monthly-on-11th",
+        ] {
+            assert_eq!(rule, rule.parse::<Recurrence>().unwrap().to_string());
+        }
+        for rule in [
+            "",
+            "0d",
+            "2x",
+            "w",
+            "monthly-on-32nd",
+            "monthly-on-1th",
+            "+",
+        ] {
+            assert!(rule.parse::<Recurrence>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_next_date_out_of_range() {
+        for rule in [
+            "999999999d",
+            "99999999w",
+            "99999999m",
+            "99999999y",
+            "400000000y",
+        ] {
+            let recurrence: Recurrence = rule.parse().unwrap();
+
+            assert_eq!(None, recurrence.next(None, date(10, 14)), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_relaxed_counts_from_completion() {
+        let weekly: Recurrence = "weekly".parse().unwrap();
+
+        assert_eq!(
+            Some(date(10, 21)),
+            weekly.next(Some(date(10, 10)), date(10, 14))
+        );
+    }
+
+    #[test]
+    fn test_strict_counts_from_due_date() {
+        let every_two_weeks: Recurrence = "+2w".parse().unwrap();
+        let monthly: Recurrence = "+monthly".parse().unwrap();
+
+        assert_eq!(
+            Some(date(10, 24)),
+            every_two_weeks.next(Some(date(10, 10)), date(10, 14))
+        );
+        assert_eq!(Some(date(10, 28)), every_two_weeks.next(None, date(10, 14)));
+        assert_eq!(
+            Some(date(2, 28)),
+            monthly.next(Some(date(1, 31)), date(2, 3))
+        );
+    }
+
+    #[test]
+    fn test_monthly_on_day() {
+        let first: Recurrence = "monthly-on-1st".parse().unwrap();
+        let thirty_first: Recurrence = "monthly-on-31st".parse().unwrap();
+
+        assert_eq!(Some(date(11, 1)), first.next(None, date(10, 1)));
+        assert_eq!(Some(date(11, 1)), first.next(None, date(10, 14)));
+        assert_eq!(Some(date(10, 31)), thirty_first.next(None, date(10, 14)));
+        assert_eq!(Some(date(11, 30)), thirty_first.next(None, date(10, 31)));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{Source, Status, Task};
+use ignore::WalkBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const KEYWORDS: [&str; 3] = ["TODO:", "FIXME:", "XXX:"];
+
+/// A `TODO:`, `FIXME:` or `XXX:` comment found in a source file
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    pub path: PathBuf,
+    pub line: usize,
+    pub text: String,
+}
+
+impl Comment {
+    /// Whether `task` was created from this comment, regardless of the line
+    pub fn is_imported_as(&self, task: &Task) -> bool {
+        match &task.source {
+            Some(source) => task.text == self.text && source.path == self.path,
+            None => false,
+        }
+    }
+
+    /// Task linked to this comment
+    pub fn to_task(&self) -> Task {
+        Task {
+            text: self.text.clone(),
+            status: Status::Unfinished,
+            source: Some(Source {
+                path: self.path.clone(),
+                line: self.line,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+/// Comment markers of the languages we know, `None` for unknown files
+fn comment_markers(path: &Path) -> Option<&'static [&'static str]> {
+    let extension = path.extension()?.to_str()?;
+    let markers: &'static [&'static str] = match extension {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "go" | "java" | "kt" | "js" | "jsx"
+        | "ts" | "tsx" | "swift" | "scala" | "dart" | "zig" => &["//", "/*", "*"],
+        "py" | "sh" | "bash" | "zsh" | "fish" | "rb" | "pl" | "r" | "toml" | "yaml" | "yml"
+        | "cfg" | "ini" | "mk" | "cmake" | "nix" | "ex" | "exs" => &["#"],
+        "sql" | "lua" | "hs" | "elm" => &["--"],
+        "html" | "xml" | "md" | "vue" | "svelte" => &["<!--"],
+        "css" | "scss" => &["/*", "*"],
+        "php" => &["//", "#", "/*", "*"],
+        "lisp" | "clj" | "el" | "scm" => &[";"],
+        _ => return None,
+    };
+    Some(markers)
+}
+
+/// Finds the keyword comments in the lines of a file, returning the 1-based
+/// line number, the byte offset of the comment marker and the text of the comment
+fn find_comments(content: &str, markers: &[&str]) -> Vec<(usize, usize, String)> {
+    let mut comments = vec![];
+    for (number, line) in content.lines().enumerate() {
+        let keyword_start = KEYWORDS
+            .iter()
+            .filter_map(|keyword| line.find(keyword).map(|start| (start, *keyword)))
+            .min();
+        let (start, keyword) = match keyword_start {
+            Some(keyword_start) => keyword_start,
+            None => continue,
+        };
+        // the keyword has to start the comment and mustn't be inside a string
+        let before = &line[..start];
+        let is_in_string = before.matches('"').count() % 2 == 1;
+        let comment_start = before.trim_end().trim_end_matches('!');
+        let marker = markers
+            .iter()
+            .find(|marker| comment_start.ends_with(*marker));
+        let column = match marker {
+            Some(marker) if !is_in_string => comment_start.len() - marker.len(),
+            _ => continue,
+        };
+        let mut text = line[start + keyword.len()..].trim();
+        for terminator in ["*/", "-->"] {
+            text = text.strip_suffix(terminator).unwrap_or(text).trim_end();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let text = match keyword {
+            "TODO:" => text.to_string(),
+            _ => format!("{} {}", keyword, text),
+        };
+        comments.push((number + 1, column, text));
+    }
+    comments
+}
+
+/// Finds all keyword comments in the files below `paths`, skipping
+/// everything ignored by git
+pub fn scan(paths: &[PathBuf]) -> Vec<Comment> {
+    let mut comments = vec![];
+    for root in paths {
+        let walk = WalkBuilder::new(root)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .build();
+        for entry in walk.flatten() {
+            let path = entry.path();
+            let markers = match comment_markers(path) {
+                Some(markers) if path.is_file() => markers,
+                _ => continue,
+            };
+            let content = match fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let path = path.strip_prefix(".").unwrap_or(path);
+            for (line, _, text) in find_comments(&content, markers) {
+                comments.push(Comment {
+                    path: path.to_path_buf(),
+                    line,
+                    text,
+                });
+            }
+        }
+    }
+    comments
+}
+
+/// `path` as written in the `src:` tags of a list in `dir`: relative to
+/// `dir` if the file is inside it, otherwise as it is
+pub fn relative_path(dir: &Path, path: &Path) -> PathBuf {
+    let (dir, absolute) = match (fs::canonicalize(dir), fs::canonicalize(path)) {
+        (Ok(dir), Ok(absolute)) => (dir, absolute),
+        _ => return path.to_path_buf(),
+    };
+    match absolute.strip_prefix(&dir) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// All keyword comments in a single file, `None` if it can't be read
+pub fn comments_in(path: &Path) -> Option<Vec<Comment>> {
+    let content = fs::read_to_string(path).ok()?;
+    let markers = comment_markers(path)?;
+    let comments = find_comments(&content, markers)
+        .into_iter()
+        .map(|(line, _, text)| Comment {
+            path: path.to_path_buf(),
+            line,
+            text,
+        })
+        .collect();
+    Some(comments)
+}
+
+/// Removes the keyword comment in `line` of the file. Lines, that only consist
+/// of the comment, are removed entirely.
+pub fn remove_comment(path: &Path, line: usize) -> bool {
+    let (content, markers) = match (fs::read_to_string(path), comment_markers(path)) {
+        (Ok(content), Some(markers)) => (content, markers),
+        _ => return false,
+    };
+    let column = match find_comments(&content, markers)
+        .into_iter()
+        .find(|(comment_line, _, _)| *comment_line == line)
+    {
+        Some((_, column, _)) => column,
+        None => return false,
+    };
+    let mut lines: Vec<&str> = content.lines().collect();
+    let code = lines[line - 1][..column].trim_end();
+    if code.is_empty() {
+        lines.remove(line - 1);
+    } else {
+        lines[line - 1] = code;
+    }
+    let mut new_content = lines.join("\n");
+    if content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    fs::write(path, new_content).unwrap();
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_fs::fixture::TempDir;
+
+    #[test]
+    fn test_find_rust_comments() {
+        let content = r#"pub fn execute(&self) {
+        // TODO: add a Result as return value
+    let file_content = fs::read_to_string(file_path).expect("Couldn't read file contents"); // TODO: make these two lines their own function
+    /* FIXME: block comment */
+     * XXX: inside a doc block
+    let todo = "TODO: a string";
+    let comment = "// TODO: a string";
+    /// Finds `TODO:` comments
+    //! TODO: inner doc comment
+"#;
+
+        assert_eq!(
+            vec![
+                (2, 8, "add a Result as return value".to_string()),
+                (3, 92, "make these two lines their own function".to_string()),
+                (4, 4, "FIXME: block comment".to_string()),
+                (5, 5, "XXX: inside a doc block".to_string()),
+                (9, 4, "inner doc comment".to_string()),
+            ],
+            find_comments(content, comment_markers(Path::new("lib.rs")).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_find_hash_comments() {
+        let content = "# TODO: pin the version\nx = 1 # FIXME: magic number\n// TODO: not python\n";
+
+        assert_eq!(
+            vec![
+                (1, 0, "pin the version".to_string()),
+                (2, 6, "FIXME: magic number".to_string()),
+            ],
+            find_comments(content, comment_markers(Path::new("setup.py")).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_is_imported_as_ignores_line() {
+        let comment = Comment {
+            path: PathBuf::from("src/lib.rs"),
+            line: 10,
+            text: "can I remove the loops".to_string(),
+        };
+
+        let imported_as = |line: &str| comment.is_imported_as(&line.parse().unwrap());
+
+        assert!(imported_as("[ ] can I remove the loops src:src/lib.rs:3"));
+        assert!(!imported_as(
+            "[ ] can I remove the loops src:src/main.rs:10"
+        ));
+        assert!(!imported_as("[ ] can I remove the loops"));
+    }
+
+    #[test]
+    fn test_unknown_files_are_skipped() {
+        assert_eq!(None, comment_markers(Path::new("requests.jsonl")));
+        assert_eq!(None, comment_markers(Path::new(".todo")));
+    }
+
+    #[test]
+    fn test_scan_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::create_dir(temp_dir.path().join("target")).unwrap();
+        fs::write(temp_dir.path().join(".gitignore"), "target\n").unwrap();
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "fn main() {}\n// TODO: can I remove the loops\n",
+        )
+        .unwrap();
+        fs::write(temp_dir.path().join("target/gen.rs"), "// TODO: ignored\n").unwrap();
+
+        let comments = scan(&[temp_dir.path().to_path_buf()]);
+
+        assert_eq!(
+            vec![Comment {
+                path: temp_dir.path().join("src/lib.rs"),
+                line: 2,
+                text: "can I remove the loops".to_string()
+            }],
+            comments
+        );
+        assert_eq!(
+            format!(
+                "[ ] can I remove the loops src:{}:2",
+                temp_dir.path().join("src/lib.rs").display()
+            ),
+            comments[0].to_task().to_string()
+        );
+    }
+
+    #[test]
+    fn test_remove_comment() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("command.rs");
+        fs::write(
+            &path,
+            "fn check() {\n    // TODO: maybe use mark or toggle instead of check\n    let a = 1; // TODO: make these two lines their own function\n}\n",
+        )
+        .unwrap();
+
+        assert!(remove_comment(&path, 3));
+        assert!(remove_comment(&path, 2));
+        assert!(!remove_comment(&path, 1));
+
+        assert_eq!(
+            "fn check() {\n    let a = 1;\n}\n",
+            fs::read_to_string(&path).unwrap()
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use crate::command::{
+    add_in, check_in, dedupe_in, edit_in, move_in, remove_in, stop_tracking, uncheck_in,
+};
+use crate::status::Markers;
+use crate::{Error, TaskList};
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// A list held in memory across several commands, as used by `todo shell`.
+/// The operations print the same messages as the commands of the same name,
+/// but nothing is written to disk until `flush` is called.
+pub struct Session {
+    file_path: PathBuf,
+    task_list: TaskList,
+    today: NaiveDate,
+    changed: bool,
+}
+
+impl Session {
+    pub fn open(file_path: &Path, markers: &Markers, today: NaiveDate) -> Result<Session, Error> {
+        let file_content = fs::read_to_string(file_path).map_err(|_| Error::NoList)?;
+        Ok(Session {
+            file_path: file_path.to_path_buf(),
+            task_list: TaskList::parse_with(&file_content, markers)?,
+            today,
+            changed: false,
+        })
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Index and text of every item, as displayed by `list`
+    pub fn tasks(&self) -> Vec<(usize, String)> {
+        self.task_list
+            .display_order()
+            .into_iter()
+            .enumerate()
+            .map(|(index, position)| (index + 1, self.task_list.tasks[position].text.clone()))
+            .collect()
+    }
+
+    pub fn list(&self, show_dates: bool, writer: &mut impl Write) {
+        write!(writer, "{}", self.task_list.listing(show_dates)).unwrap();
+    }
+
+    pub fn add(
+        &mut self,
+        text: &str,
+        duplicate_threshold: Option<f64>,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        add_in(
+            &mut self.task_list,
+            text,
+            self.today,
+            duplicate_threshold,
+            writer,
+        )?;
+        self.changed = true;
+        Ok(())
+    }
+
+    /// Checks an item. Unlike the list, the time log is written right away,
+    /// if the item was being tracked.
+    pub fn check(
+        &mut self,
+        item_index: usize,
+        now: NaiveDateTime,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        let checked_task = check_in(&mut self.task_list, item_index, self.today, writer)?;
+        self.changed = true;
+        stop_tracking(&self.file_path, &self.task_list, &checked_task, now, writer)
+    }
+
+    pub fn uncheck(&mut self, item_index: usize, writer: &mut impl Write) -> Result<(), Error> {
+        uncheck_in(&mut self.task_list, item_index, writer)?;
+        self.changed = true;
+        Ok(())
+    }
+
+    pub fn edit(
+        &mut self,
+        item_index: usize,
+        text: &str,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        edit_in(&mut self.task_list, item_index, text, writer)?;
+        self.changed = true;
+        Ok(())
+    }
+
+    pub fn remove(&mut self, item_index: usize, writer: &mut impl Write) -> Result<(), Error> {
+        remove_in(&mut self.task_list, item_index, writer)?;
+        self.changed = true;
+        Ok(())
+    }
+
+    pub fn move_task(
+        &mut self,
+        item_index: usize,
+        new_index: usize,
+        writer: &mut impl Write,
+    ) -> Result<(), Error> {
+        move_in(&mut self.task_list, item_index, new_index, writer)?;
+        self.changed = true;
+        Ok(())
+    }
+
+    pub fn dedupe(&mut self, threshold: f64, writer: &mut impl Write) {
+        self.changed |= dedupe_in(&mut self.task_list, threshold, writer) > 0;
+    }
+
+    /// Writes the list, if it changed. The list is written to a temporary
+    /// file next to it first and renamed, so the file is never left half
+    /// written.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if !self.changed {
+            return Ok(());
+        }
+        let cannot_create = |_| Error::CannotCreate(self.file_path.display().to_string());
+        let dir = match self.file_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut temp_file = NamedTempFile::new_in(dir).map_err(cannot_create)?;
+        // the temporary file is only readable by its owner, unlike the list
+        if let Ok(metadata) = fs::metadata(&self.file_path) {
+            temp_file
+                .as_file()
+                .set_permissions(metadata.permissions())
+                .map_err(cannot_create)?;
+        }
+        temp_file
+            .write_all(self.task_list.to_string_unordered().as_bytes())
+            .map_err(cannot_create)?;
+        temp_file
+            .persist(&self.file_path)
+            .map_err(|error| cannot_create(error.error))?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// Reads the list from disk again, e.g. after another command changed it
+    pub fn reload(&mut self) -> Result<(), Error> {
+        let markers = self.task_list.markers.clone();
+        *self = Session::open(&self.file_path, &markers, self.today)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_fs::fixture::TempDir;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, 10, 14).unwrap()
+    }
+
+    #[test]
+    fn test_changes_are_only_written_on_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n").unwrap();
+        let mut session = Session::open(&file_path, &Markers::default(), day()).unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        session.add("Task 2", None, &mut writer).unwrap();
+        session.move_task(2, 1, &mut writer).unwrap();
+        session
+            .check(2, day().and_hms_opt(12, 0, 0).unwrap(), &mut writer)
+            .unwrap();
+
+        assert_eq!("[ ] Task 1\n", fs::read_to_string(&file_path).unwrap());
+        session.flush().unwrap();
+        assert_eq!(
+            "[ ] 2022-10-14 Task 2\n[x] 2022-10-14 Task 1\n",
+            fs::read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(1, fs::read_dir(temp_dir.path()).unwrap().count());
+        assert_eq!(
+            "Added: Task 2\nMoved: [ ] Task 2\n[x] Task 1\n",
+            String::from_utf8(writer).unwrap()
+        );
+        assert_eq!(
+            vec![(1, "Task 2".to_string()), (2, "Task 1".to_string())],
+            session.tasks()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_flush_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n").unwrap();
+        fs::set_permissions(&file_path, fs::Permissions::from_mode(0o644)).unwrap();
+        let mut session = Session::open(&file_path, &Markers::default(), day()).unwrap();
+
+        session.remove(1, &mut Vec::new()).unwrap();
+        session.flush().unwrap();
+
+        let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+        assert_eq!(0o644, mode & 0o777);
+    }
+
+    #[test]
+    fn test_errors_leave_list_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n").unwrap();
+        let mut session = Session::open(&file_path, &Markers::default(), day()).unwrap();
+
+        assert_eq!(
+            Err(Error::IndexOutOfBounds),
+            session.remove(2, &mut Vec::new())
+        );
+        assert!(matches!(
+            session.add("task 1", Some(0.9), &mut Vec::new()),
+            Err(Error::Duplicate(_))
+        ));
+        assert!(!session.changed);
+    }
+
+    #[test]
+    fn test_check_stops_tracking() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Invoice id:1\n").unwrap();
+        fs::write(
+            temp_dir.path().join(".todo.time"),
+            "1 2022-10-14T09:00:00\n",
+        )
+        .unwrap();
+        let mut session = Session::open(&file_path, &Markers::default(), day()).unwrap();
+        let mut writer = Vec::<u8>::new();
+
+        session
+            .check(1, day().and_hms_opt(9, 45, 0).unwrap(), &mut writer)
+            .unwrap();
+
+        assert_eq!(
+            "[x] Invoice id:1\nStopped: Invoice (0h 45m)\n",
+            String::from_utf8(writer).unwrap()
+        );
+        assert_eq!(
+            "1 2022-10-14T09:00:00 2022-10-14T09:45:00\n",
+            fs::read_to_string(temp_dir.path().join(".todo.time")).unwrap()
+        );
+    }
+}
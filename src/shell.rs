@@ -0,0 +1,248 @@
+use crate::arg_parsing::{confirm_duplicate, Args, Commands};
+use chrono::Local;
+use clap::{CommandFactory, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::path::Path;
+use todo_rs::command::list_path;
+use todo_rs::config::Config;
+use todo_rs::session::Session;
+use todo_rs::Error;
+
+/// Completes subcommands as the first word and items afterwards, either by
+/// index or by text
+struct ShellHelper {
+    subcommands: Vec<String>,
+    tasks: Vec<(usize, String)>,
+}
+
+impl ShellHelper {
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<Pair>) {
+        let start = line[..pos].rfind(' ').map_or(0, |space| space + 1);
+        let word = &line[start..pos];
+        let candidates = if start == 0 {
+            self.subcommands
+                .iter()
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair {
+                    display: name.clone(),
+                    replacement: format!("{} ", name),
+                })
+                .collect()
+        } else if word.chars().all(|c| c.is_ascii_digit()) {
+            self.tasks
+                .iter()
+                .filter(|(index, _)| index.to_string().starts_with(word))
+                .map(|(index, text)| Pair {
+                    display: format!("{} {}", index, text),
+                    replacement: index.to_string(),
+                })
+                .collect()
+        } else {
+            let prefix = word.trim_start_matches(['"', '\'']).to_lowercase();
+            self.tasks
+                .iter()
+                .filter(|(_, text)| text.to_lowercase().starts_with(&prefix))
+                .map(|(_, text)| Pair {
+                    display: text.clone(),
+                    replacement: shell_words::quote(text).to_string(),
+                })
+                .collect()
+        };
+        (start, candidates)
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Runs a command on the list kept in memory. Commands, that work on other
+/// lists or files, run as usual on the flushed list.
+fn execute(session: &mut Session, args: &Args, list_name: Option<&str>) -> Result<(), Error> {
+    let stdout = &mut std::io::stdout();
+    if args.list.as_deref() != list_name {
+        session.flush()?;
+        args.command.execute(args.list.as_deref())?;
+        return session.reload();
+    }
+    match &args.command {
+        Commands::List {
+            recursive: false,
+            estimates: false,
+            dates,
+            ..
+        } => session.list(*dates, stdout),
+        Commands::Add { text, force } => {
+            let threshold = match force {
+                true => None,
+                false => Some(Config::load(Path::new(".")).similarity_threshold),
+            };
+            match session.add(text, threshold, stdout) {
+                Err(Error::Duplicate(task)) if confirm_duplicate(&task) => {
+                    session.add(text, None, stdout)?
+                }
+                result => result?,
+            }
+        }
+        Commands::Check {
+            item,
+            commit: false,
+        } if item.path.is_none() => {
+            session.check(item.index, Local::now().naive_local(), stdout)?
+        }
+        Commands::Uncheck { item_index } => session.uncheck(*item_index, stdout)?,
+        Commands::Edit { item_index, text } => session.edit(*item_index, text, stdout)?,
+        Commands::Remove { item } if item.path.is_none() => session.remove(item.index, stdout)?,
+        Commands::Move {
+            item_index,
+            new_index,
+        } => session.move_task(*item_index, *new_index, stdout)?,
+        Commands::Top { item_index } => session.move_task(*item_index, 1, stdout)?,
+        Commands::Bottom { item_index } => session.move_task(*item_index, usize::MAX, stdout)?,
+        Commands::Dedupe => {
+            let threshold = Config::load(Path::new(".")).similarity_threshold;
+            session.dedupe(threshold, stdout);
+        }
+        Commands::Shell => println!("Already in the shell"),
+        command => {
+            session.flush()?;
+            command.execute(list_name)?;
+            if session.file_path().exists() {
+                session.reload()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads commands until `exit` or end of input and writes the list at the end
+pub fn run(list_name: Option<&str>) -> Result<(), Error> {
+    let file_path = list_path(Path::new("."), list_name);
+    let markers = Config::load(Path::new(".")).markers;
+    let mut session = Session::open(&file_path, &markers, Local::now().date_naive())?;
+    let mut subcommands: Vec<String> = Args::command()
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    subcommands.push("exit".to_string());
+    let mut editor: Editor<ShellHelper, DefaultHistory> =
+        Editor::new().expect("Couldn't start the shell");
+    editor.set_helper(Some(ShellHelper {
+        subcommands,
+        tasks: session.tasks(),
+    }));
+
+    loop {
+        let line = match editor.readline("todo> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(_) => break,
+        };
+        let words = match shell_words::split(&line) {
+            Ok(words) => words,
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                continue;
+            }
+        };
+        match words.first().map(String::as_str) {
+            None => continue,
+            Some("exit") | Some("quit") => break,
+            Some(_) => {}
+        }
+        editor.add_history_entry(line.as_str()).unwrap();
+        match Args::try_parse_from(std::iter::once("todo".to_string()).chain(words)) {
+            Ok(args) => {
+                if let Err(error) = execute(&mut session, &args, list_name) {
+                    eprintln!("Error: {}", error);
+                }
+            }
+            Err(error) => error.print().unwrap(),
+        }
+        if let Some(helper) = editor.helper_mut() {
+            helper.tasks = session.tasks();
+        }
+        // `destroy` leaves nothing to write back
+        if !session.file_path().exists() {
+            return Ok(());
+        }
+    }
+    session.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn helper() -> ShellHelper {
+        ShellHelper {
+            subcommands: vec![
+                "check".to_string(),
+                "create".to_string(),
+                "edit".to_string(),
+            ],
+            tasks: vec![
+                (1, "Write tests".to_string()),
+                (2, "Fix bug".to_string()),
+                (12, "write docs".to_string()),
+            ],
+        }
+    }
+
+    fn replacements(line: &str) -> (usize, Vec<String>) {
+        let (start, candidates) = helper().candidates(line, line.len());
+        let replacements = candidates
+            .into_iter()
+            .map(|candidate| candidate.replacement)
+            .collect();
+        (start, replacements)
+    }
+
+    #[test]
+    fn test_complete_subcommands() {
+        assert_eq!(
+            (0, vec!["check ".to_string(), "create ".to_string()]),
+            replacements("c")
+        );
+    }
+
+    #[test]
+    fn test_complete_indices_and_texts() {
+        assert_eq!(
+            (6, vec!["1".to_string(), "12".to_string()]),
+            replacements("check 1")
+        );
+        assert_eq!(
+            (
+                7,
+                vec!["'Write tests'".to_string(), "'write docs'".to_string()]
+            ),
+            replacements("edit 1 \"wri")
+        );
+    }
+}
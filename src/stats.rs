@@ -0,0 +1,252 @@
+use crate::{ParseError, Status, Task};
+use chrono::{Datelike, NaiveDate};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        match input {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+/// Number of tasks added and completed in a day or week
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct Activity {
+    pub added: usize,
+    pub completed: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub total: usize,
+    pub open: usize,
+    pub done: usize,
+    /// Cancelled tasks are neither open nor done
+    pub cancelled: usize,
+    pub per_day: BTreeMap<NaiveDate, Activity>,
+    /// Keyed by ISO week, e.g. `2022-W41`
+    pub per_week: BTreeMap<String, Activity>,
+    /// Average days from creation to completion, for tasks with both dates
+    pub average_days_to_complete: Option<f64>,
+    pub oldest_open: Option<Task>,
+}
+
+fn week(date: NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+impl Stats {
+    /// Collects the statistics of `tasks`. The activity and the time to
+    /// complete only take dates on or after `since` into account.
+    pub fn new(tasks: &[Task], since: Option<NaiveDate>) -> Stats {
+        let is_recent = |date: &NaiveDate| since.is_none_or(|since| *date >= since);
+        let mut per_day: BTreeMap<NaiveDate, Activity> = BTreeMap::new();
+        let mut days_to_complete = vec![];
+        for task in tasks {
+            if let Some(created) = task.created.filter(is_recent) {
+                per_day.entry(created).or_default().added += 1;
+            }
+            if let Some(completed) = task.completed.filter(is_recent) {
+                per_day.entry(completed).or_default().completed += 1;
+                if let Some(created) = task.created {
+                    days_to_complete.push((completed - created).num_days());
+                }
+            }
+        }
+        let mut per_week: BTreeMap<String, Activity> = BTreeMap::new();
+        for (day, activity) in per_day.iter() {
+            let week = per_week.entry(week(*day)).or_default();
+            week.added += activity.added;
+            week.completed += activity.completed;
+        }
+        let average_days_to_complete = match days_to_complete.len() {
+            0 => None,
+            count => Some(days_to_complete.iter().sum::<i64>() as f64 / count as f64),
+        };
+        let oldest_open = tasks
+            .iter()
+            .filter(|task| !task.status.is_closed())
+            .filter(|task| task.created.is_some())
+            .min_by_key(|task| task.created)
+            .cloned();
+        let done = tasks.iter().filter(|task| task.status.is_done()).count();
+        let cancelled = tasks
+            .iter()
+            .filter(|task| task.status == Status::Cancelled)
+            .count();
+
+        Stats {
+            total: tasks.len(),
+            open: tasks.len() - done - cancelled,
+            done,
+            cancelled,
+            per_day,
+            per_week,
+            average_days_to_complete,
+            oldest_open,
+        }
+    }
+
+    /// Percentage of finished tasks, not counting the cancelled ones
+    pub fn completion(&self) -> f64 {
+        match self.total - self.cancelled {
+            0 => 0.0,
+            total => self.done as f64 * 100.0 / total as f64,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let activity =
+            |activity: &Activity| json!({"added": activity.added, "completed": activity.completed});
+        json!({
+            "total": self.total,
+            "open": self.open,
+            "done": self.done,
+            "cancelled": self.cancelled,
+            "completion": self.completion(),
+            "average_days_to_complete": self.average_days_to_complete,
+            "oldest_open": self.oldest_open.as_ref().map(|task| task.to_string()),
+            "per_day": self.per_day.iter()
+                .map(|(day, counts)| (day.to_string(), activity(counts)))
+                .collect::<serde_json::Map<String, serde_json::Value>>(),
+            "per_week": self.per_week.iter()
+                .map(|(week, counts)| (week.clone(), activity(counts)))
+                .collect::<serde_json::Map<String, serde_json::Value>>(),
+        })
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total: {}", self.total)?;
+        writeln!(f, "Open: {}", self.open)?;
+        writeln!(f, "Done: {} ({:.0}%)", self.done, self.completion())?;
+        if self.cancelled > 0 {
+            writeln!(f, "Cancelled: {}", self.cancelled)?;
+        }
+        if let Some(days) = self.average_days_to_complete {
+            writeln!(f, "Average time to complete: {:.1} days", days)?;
+        }
+        if let Some(task) = &self.oldest_open {
+            writeln!(f, "Oldest open item: {}", task)?;
+        }
+        if !self.per_day.is_empty() {
+            writeln!(f, "\nPer day:")?;
+            for (day, activity) in self.per_day.iter() {
+                writeln!(
+                    f,
+                    "{}  {} added, {} completed",
+                    day, activity.added, activity.completed
+                )?;
+            }
+            writeln!(f, "\nPer week:")?;
+            for (week, activity) in self.per_week.iter() {
+                writeln!(
+                    f,
+                    "{}  {} added, {} completed",
+                    week, activity.added, activity.completed
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tasks() -> Vec<Task> {
+        "[x] 2022-10-14 2022-10-10 Write tests\n\
+         [x] 2022-10-17 2022-10-14 Fix bug\n\
+         [ ] 2022-10-14 Release\n\
+         [ ] 2022-10-01 Update README\n\
+         [ ] Undated"
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(&tasks(), None);
+
+        assert_eq!(5, stats.total);
+        assert_eq!(3, stats.open);
+        assert_eq!(2, stats.done);
+        assert_eq!(40.0, stats.completion());
+        assert_eq!(Some(3.5), stats.average_days_to_complete);
+        assert_eq!(
+            Some("[ ] 2022-10-01 Update README".parse().unwrap()),
+            stats.oldest_open
+        );
+        let day = NaiveDate::from_ymd_opt(2022, 10, 14).unwrap();
+        assert_eq!(
+            Activity {
+                added: 2,
+                completed: 1
+            },
+            stats.per_day[&day]
+        );
+        assert_eq!(
+            vec!["2022-W39", "2022-W41", "2022-W42"],
+            stats.per_week.keys().collect::<Vec<&String>>()
+        );
+        assert_eq!(
+            Activity {
+                added: 3,
+                completed: 1
+            },
+            stats.per_week["2022-W41"]
+        );
+    }
+
+    #[test]
+    fn test_stats_since() {
+        let since = NaiveDate::from_ymd_opt(2022, 10, 15);
+
+        let stats = Stats::new(&tasks(), since);
+
+        assert_eq!(5, stats.total);
+        assert_eq!(Some(3.0), stats.average_days_to_complete);
+        assert_eq!(1, stats.per_day.len());
+    }
+
+    #[test]
+    fn test_cancelled_tasks_are_neither_open_nor_done() {
+        let mut tasks = tasks();
+        tasks.push("[-] 2022-10-02 Rewrite in Go".parse().unwrap());
+
+        let stats = Stats::new(&tasks, None);
+
+        assert_eq!(6, stats.total);
+        assert_eq!(3, stats.open);
+        assert_eq!(1, stats.cancelled);
+        assert_eq!(40.0, stats.completion());
+        assert!(stats.to_string().contains("Done: 2 (40%)\nCancelled: 1\n"));
+    }
+
+    #[test]
+    fn test_empty_stats() {
+        let stats = Stats::new(&[], None);
+
+        assert_eq!(0.0, stats.completion());
+        assert_eq!("Total: 0\nOpen: 0\nDone: 0 (0%)\n", stats.to_string());
+        assert_eq!(json!(null), stats.to_json()["average_days_to_complete"]);
+    }
+}
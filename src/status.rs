@@ -0,0 +1,257 @@
+use std::fmt;
+
+#[derive(PartialEq, Debug, Clone, Default)]
+pub enum Status {
+    #[default]
+    Unfinished,
+    InProgress,
+    Waiting,
+    Blocked,
+    Finished,
+    Cancelled,
+    /// A status defined in the configuration
+    Custom(Box<CustomStatus>),
+    /// A marker nobody defined, that is kept as it is
+    Other(char),
+}
+
+/// The statuses, that `todo` knows without configuration, by their name in
+/// `.todo.toml`
+const BUILT_IN: [(&str, Status); 6] = [
+    ("open", Status::Unfinished),
+    ("in-progress", Status::InProgress),
+    ("waiting", Status::Waiting),
+    ("blocked", Status::Blocked),
+    ("done", Status::Finished),
+    ("cancelled", Status::Cancelled),
+];
+
+/// Markers, that are read as a built-in status, unless they're configured
+/// for another one
+const VARIANTS: [(char, Status); 2] = [('✓', Status::Finished), ('✔', Status::Finished)];
+
+/// Rank of markers nobody defined and of custom statuses without a rank,
+/// between waiting and finished tasks
+pub const OTHER_RANK: u32 = 50;
+
+/// A status of a project's own, e.g. `[r]` for items in review
+#[derive(Debug, PartialEq, Clone)]
+pub struct CustomStatus {
+    pub name: String,
+    pub marker: char,
+    /// Position in listings, compared to the ranks of the built-in statuses:
+    /// in progress 10, open 20, blocked 30, waiting 40, done 60, cancelled 70
+    pub rank: u32,
+    /// Whether items with this status count as done
+    pub done: bool,
+}
+
+/// The markers of all statuses of a project, set in `.todo.toml`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Markers {
+    /// The marker written for each built-in status
+    pub built_in: Vec<(Status, char)>,
+    pub custom: Vec<CustomStatus>,
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        Markers {
+            built_in: vec![
+                (Status::Unfinished, ' '),
+                (Status::InProgress, '~'),
+                (Status::Waiting, '?'),
+                (Status::Blocked, '!'),
+                (Status::Finished, 'x'),
+                (Status::Cancelled, '-'),
+            ],
+            custom: vec![],
+        }
+    }
+}
+
+fn same_marker(a: char, b: char) -> bool {
+    a.to_lowercase().eq(b.to_lowercase())
+}
+
+impl Markers {
+    /// Sets the marker written for the built-in status called `name`.
+    /// Returns `false` if there is no such status.
+    pub fn set_built_in(&mut self, name: &str, marker: char) -> bool {
+        let status = match BUILT_IN.iter().find(|(built_in, _)| *built_in == name) {
+            Some((_, status)) => status,
+            None => return false,
+        };
+        for (built_in, built_in_marker) in self.built_in.iter_mut() {
+            if built_in == status {
+                *built_in_marker = marker;
+            }
+        }
+        true
+    }
+
+    /// The character between the brackets, e.g. `x` for `[x]`
+    pub fn marker(&self, status: &Status) -> char {
+        match status {
+            Status::Custom(custom) => custom.marker,
+            Status::Other(marker) => *marker,
+            status => {
+                self.built_in
+                    .iter()
+                    .find(|(built_in, _)| built_in == status)
+                    .unwrap()
+                    .1
+            }
+        }
+    }
+
+    fn built_in_status(&self, marker: char) -> Option<Status> {
+        self.built_in
+            .iter()
+            .find(|(_, built_in_marker)| same_marker(*built_in_marker, marker))
+            .map(|(status, _)| status.clone())
+    }
+
+    /// The status written with `marker`. The configured markers are matched
+    /// case-insensitively, then the default ones and common variants, like
+    /// `[X]` or `[✓]`.
+    pub fn parse(&self, marker: char) -> Status {
+        let custom = self
+            .custom
+            .iter()
+            .find(|custom| same_marker(custom.marker, marker))
+            .map(|custom| Status::Custom(Box::new(custom.clone())));
+        let variant = VARIANTS
+            .iter()
+            .find(|(variant, _)| *variant == marker)
+            .map(|(_, status)| status.clone());
+        self.built_in_status(marker)
+            .or(custom)
+            .or_else(|| Markers::default().built_in_status(marker))
+            .or(variant)
+            .unwrap_or(Status::Other(marker))
+    }
+
+    /// The built-in or custom status called `name`
+    pub fn status(&self, name: &str) -> Option<Status> {
+        match BUILT_IN.iter().find(|(built_in, _)| *built_in == name) {
+            Some((_, status)) => Some(status.clone()),
+            None => self
+                .custom
+                .iter()
+                .find(|custom| custom.name == name)
+                .map(|custom| Status::Custom(Box::new(custom.clone()))),
+        }
+    }
+}
+
+impl Status {
+    /// Used in exports, statistics and as column of `todo board`
+    pub fn name(&self) -> String {
+        match self {
+            Status::Custom(custom) => custom.name.clone(),
+            Status::Other(_) => "other".to_string(),
+            status => {
+                let (name, _) = BUILT_IN
+                    .iter()
+                    .find(|(_, built_in)| built_in == status)
+                    .unwrap();
+                name.to_string()
+            }
+        }
+    }
+
+    /// Position of the status in listings, see `CustomStatus::rank`
+    pub fn rank(&self) -> u32 {
+        match self {
+            Status::InProgress => 10,
+            Status::Unfinished => 20,
+            Status::Blocked => 30,
+            Status::Waiting => 40,
+            Status::Finished => 60,
+            Status::Cancelled => 70,
+            Status::Custom(custom) => custom.rank,
+            Status::Other(_) => OTHER_RANK,
+        }
+    }
+
+    /// Finished tasks and tasks with a custom status, that counts as done
+    pub fn is_done(&self) -> bool {
+        match self {
+            Status::Finished => true,
+            Status::Custom(custom) => custom.done,
+            _ => false,
+        }
+    }
+
+    /// Done and cancelled tasks need no more work
+    pub fn is_closed(&self) -> bool {
+        self.is_done() || *self == Status::Cancelled
+    }
+}
+
+/// `[x]`, written with the default markers
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", Markers::default().marker(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn review() -> CustomStatus {
+        CustomStatus {
+            name: "review".to_string(),
+            marker: 'r',
+            rank: 15,
+            done: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_variants_case_insensitively() {
+        let mut markers = Markers::default();
+        markers.set_built_in("done", '✓');
+        markers.custom.push(review());
+
+        for marker in ['x', 'X', '✓', '✔'] {
+            assert_eq!(Status::Finished, markers.parse(marker));
+        }
+        assert_eq!(Status::Custom(Box::new(review())), markers.parse('R'));
+        assert_eq!(Status::Other('*'), markers.parse('*'));
+        assert_eq!('✓', markers.marker(&Status::Finished));
+        assert!(!markers.set_built_in("finished", 'f'));
+    }
+
+    #[test]
+    fn test_configured_markers_are_written() {
+        let mut markers = Markers::default();
+        markers.set_built_in("done", 'X');
+        markers.custom.push(CustomStatus {
+            done: true,
+            ..review()
+        });
+
+        let statuses = [markers.parse('✔'), markers.parse('R')];
+        let written: Vec<char> = statuses
+            .iter()
+            .map(|status| markers.marker(status))
+            .collect();
+
+        assert_eq!(vec!['X', 'r'], written);
+        assert_eq!(
+            (true, 15, "review".to_string()),
+            (
+                statuses[1].is_done(),
+                statuses[1].rank(),
+                statuses[1].name()
+            )
+        );
+        assert_eq!(Some(Status::InProgress), markers.status("in-progress"));
+        assert_eq!(Some(statuses[1].clone()), markers.status("review"));
+        assert_eq!(None, Markers::default().status("review"));
+        assert_eq!("[x]", Status::Finished.to_string());
+    }
+}
@@ -0,0 +1,618 @@
+use crate::estimate::Estimate;
+use crate::recurrence::Recurrence;
+use crate::status::Markers;
+use crate::ParseError;
+use crate::Status;
+use chrono::NaiveDate;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Location of the source code comment a task was imported from
+#[derive(Debug, PartialEq, Clone)]
+pub struct Source {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+/// Spaces in the path are written as `%20`, so that the tag stays one word
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display().to_string();
+        let path = path.replace('%', "%25").replace(' ', "%20");
+        write!(f, "{}:{}", path, self.line)
+    }
+}
+
+impl FromStr for Source {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        match input.rsplit_once(':') {
+            Some((path, line)) if !path.is_empty() => Ok(Source {
+                path: path.replace("%20", " ").replace("%25", "%").into(),
+                line: line.parse().map_err(|_| ParseError)?,
+            }),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).ok()
+}
+
+fn parse_priority(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some(priority), None) if priority.is_ascii_uppercase() => Some(priority),
+        _ => None,
+    }
+}
+
+/// Removes all `key:value` words with a valid value and returns the values
+fn take_tags<T>(words: &mut Vec<&str>, key: &str, parse: impl Fn(&str) -> Option<T>) -> Vec<T> {
+    let mut values = vec![];
+    while let Some(value) = take_tag(words, key, &parse) {
+        values.insert(0, value);
+    }
+    values
+}
+
+/// An abbreviated or full commit SHA
+fn parse_commit(word: &str) -> Option<String> {
+    let is_sha = (7..=40).contains(&word.len()) && word.chars().all(|c| c.is_ascii_hexdigit());
+    is_sha.then(|| word.to_string())
+}
+
+/// Removes the last `key:value` word with a valid value and returns the value
+fn take_tag<T>(words: &mut Vec<&str>, key: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    let prefix = format!("{}:", key);
+    let position = words.iter().rposition(|word| {
+        word.strip_prefix(&prefix)
+            .is_some_and(|value| parse(value).is_some())
+    })?;
+    let value = parse(&words[position][prefix.len()..]);
+    words.remove(position);
+    value
+}
+
+/// A task is stored as a single line. Like in todo.txt, the dates follow the
+/// status: `[x] <completed> <created> text` or `[ ] <created> text`.
+/// Everything else is stored in `key:value` words after the text.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Task {
+    pub text: String,
+    pub status: Status,
+    pub source: Option<Source>,
+    pub created: Option<NaiveDate>,
+    pub completed: Option<NaiveDate>,
+    pub due: Option<NaiveDate>,
+    /// `A` (highest) to `Z`
+    pub priority: Option<char>,
+    pub recurrence: Option<Recurrence>,
+    pub estimate: Option<Estimate>,
+    /// `id:`s of the tasks, that have to be finished first
+    pub after: Vec<u32>,
+    /// Stable identity of the task, assigned when it's first referred to,
+    /// e.g. by the time tracking
+    pub id: Option<u32>,
+    /// SHA of the git commit, that finished the task
+    pub commit: Option<String>,
+    /// Text, that doesn't fit on the task's line, e.g. steps to reproduce
+    /// a bug. Stored as indented lines below the task.
+    pub notes: Vec<String>,
+}
+
+/// A task written with the markers of a project, see `Task::display_with`
+pub struct TaskDisplay<'a> {
+    task: &'a Task,
+    markers: &'a Markers,
+}
+
+impl fmt::Display for TaskDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let task = self.task;
+        write!(f, "[{}]", self.markers.marker(&task.status))?;
+        if let (true, Some(completed)) = (task.status.is_done(), task.completed) {
+            write!(f, " {}", completed.format(DATE_FORMAT))?;
+        }
+        if let Some(created) = task.created {
+            write!(f, " {}", created.format(DATE_FORMAT))?;
+        }
+        write!(f, " {}", task.text)?;
+        if let Some(priority) = task.priority {
+            write!(f, " pri:{}", priority)?;
+        }
+        if let Some(due) = task.due {
+            write!(f, " due:{}", due.format(DATE_FORMAT))?;
+        }
+        if let Some(recurrence) = task.recurrence {
+            write!(f, " rec:{}", recurrence)?;
+        }
+        if let Some(estimate) = task.estimate {
+            write!(f, " est:{}", estimate)?;
+        }
+        for id in task.after.iter() {
+            write!(f, " after:{}", id)?;
+        }
+        if let Some(source) = &task.source {
+            write!(f, " src:{}", source)?;
+        }
+        if let Some(id) = task.id {
+            write!(f, " id:{}", id)?;
+        }
+        if let Some(commit) = &task.commit {
+            write!(f, " commit:{}", commit)?;
+        }
+        Ok(())
+    }
+}
+
+/// The task written with the default markers
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_with(&Markers::default()).fmt(f)
+    }
+}
+
+impl Task {
+    /// The task written with the markers of a project
+    pub fn display_with<'a>(&'a self, markers: &'a Markers) -> TaskDisplay<'a> {
+        TaskDisplay {
+            task: self,
+            markers,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status == Status::Finished
+    }
+
+    /// Finishes the task `on` the given day. A task, that was done already,
+    /// keeps its completion date.
+    pub fn check(&mut self, on: NaiveDate) -> Task {
+        if !self.status.is_done() {
+            self.completed = Some(on);
+        }
+        self.status = Status::Finished;
+        self.clone()
+    }
+
+    /// The open copy of a recurring task, that was finished `on` the given day
+    pub fn next_occurrence(&self, on: NaiveDate) -> Option<Task> {
+        let recurrence = self.recurrence?;
+        Some(Task {
+            text: self.text.clone(),
+            created: Some(on),
+            due: Some(recurrence.next(self.due, on)?),
+            priority: self.priority,
+            recurrence: Some(recurrence),
+            estimate: self.estimate,
+            notes: self.notes.clone(),
+            ..Default::default()
+        })
+    }
+
+    pub fn uncheck(&mut self) -> Task {
+        self.status = Status::Unfinished;
+        self.completed = None;
+        self.commit = None;
+        self.clone()
+    }
+
+    /// Only done tasks have a completion date and commit. A task, that
+    /// becomes done, was completed `on` the given day.
+    pub fn set_status(&mut self, status: Status, on: NaiveDate) -> Task {
+        if !status.is_done() {
+            self.completed = None;
+            self.commit = None;
+        } else if !self.status.is_done() {
+            self.completed = Some(on);
+        }
+        self.status = status;
+        self.clone()
+    }
+
+    /// Words of the text starting with `+`, e.g. `billing` for `+billing`
+    pub fn tags(&self) -> Vec<&str> {
+        self.text
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix('+'))
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// The task as shown in listings, where dates are left out by default
+    pub fn without_dates(&self) -> Task {
+        Task {
+            created: None,
+            completed: None,
+            ..self.clone()
+        }
+    }
+
+    /// Reads a task written with the markers of a project
+    pub fn parse_with(input: &str, markers: &Markers) -> Result<Task, ParseError> {
+        let mut chars = input.chars();
+        let status = match (chars.next(), chars.next(), chars.next()) {
+            (Some('['), Some(marker), Some(']')) => markers.parse(marker),
+            _ => return Err(ParseError),
+        };
+        let rest = chars.as_str();
+        let mut words: Vec<&str> = rest.strip_prefix(' ').unwrap_or(rest).split(' ').collect();
+        // only done tasks have a completion date in front of the creation date
+        let date_count = if status.is_done() { 2 } else { 1 };
+        let mut dates = vec![];
+        while dates.len() < date_count && words.len() > 1 {
+            match parse_date(words[0]) {
+                Some(date) => dates.push(date),
+                None => break,
+            }
+            words.remove(0);
+        }
+        let (completed, created) = match (status.is_done(), dates.as_slice()) {
+            (true, [completed, created]) => (Some(*completed), Some(*created)),
+            (true, [completed]) => (Some(*completed), None),
+            (_, [created]) => (None, Some(*created)),
+            _ => (None, None),
+        };
+        let source = take_tag(&mut words, "src", |value| value.parse().ok());
+        let recurrence = take_tag(&mut words, "rec", |value| value.parse().ok());
+        let estimate = take_tag(&mut words, "est", |value| value.parse().ok());
+        let after = take_tags(&mut words, "after", |value| value.parse().ok());
+        let id = take_tag(&mut words, "id", |value| value.parse().ok());
+        let commit = take_tag(&mut words, "commit", parse_commit);
+        let due = take_tag(&mut words, "due", parse_date);
+        let priority = take_tag(&mut words, "pri", parse_priority);
+
+        Ok(Task {
+            text: words.join(" "),
+            status,
+            source,
+            created,
+            completed,
+            due,
+            priority,
+            recurrence,
+            estimate,
+            after,
+            id,
+            commit,
+            notes: vec![],
+        })
+    }
+}
+
+impl FromStr for Task {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        Task::parse_with(input, &Markers::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Source;
+    use crate::status::{CustomStatus, Markers};
+    use crate::Status;
+    use crate::Task;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_is_finished() {
+        let task1 = Task {
+            text: "Do this task first".to_string(),
+            status: Status::Finished,
+            ..Default::default()
+        };
+        let task2 = Task {
+            text: "Then do this task".to_string(),
+            status: Status::Unfinished,
+            ..Default::default()
+        };
+
+        assert!(task1.is_finished());
+        assert!(!task2.is_finished());
+    }
+
+    #[test]
+    fn test_parse_unfinished_task() {
+        let task = "[ ] Make things work"
+            .parse::<Task>()
+            .expect("parsing failed");
+
+        assert_eq!(
+            Task {
+                text: "Make things work".to_string(),
+                status: Status::Unfinished,
+                ..Default::default()
+            },
+            task
+        );
+    }
+
+    #[test]
+    fn test_parse_finished_task() {
+        let task = "[x] Nothing special"
+            .parse::<Task>()
+            .expect("parsing failed");
+
+        assert_eq!(
+            Task {
+                text: "Nothing special".to_string(),
+                status: Status::Finished,
+                ..Default::default()
+            },
+            task
+        );
+    }
+
+    #[test]
+    fn test_parse_extended_statuses() {
+        for (line, status) in [
+            ("[~] Write docs", Status::InProgress),
+            ("[?] Write docs", Status::Waiting),
+            ("[!] Write docs", Status::Blocked),
+            ("[-] Write docs", Status::Cancelled),
+            ("[ä] Write docs", Status::Other('ä')),
+        ] {
+            let task = line.parse::<Task>().unwrap();
+
+            assert_eq!(status, task.status);
+            assert_eq!("Write docs", task.text);
+            assert_eq!(line, task.to_string());
+        }
+        assert!("[]".parse::<Task>().is_err());
+        assert!("Write docs".parse::<Task>().is_err());
+    }
+
+    #[test]
+    fn test_finished_task_to_string() {
+        let task = Task {
+            text: "Get coffee".to_string(),
+            status: Status::Finished,
+            ..Default::default()
+        };
+
+        assert_eq!("[x] Get coffee", task.to_string());
+    }
+
+    #[test]
+    fn test_parse_task_with_source() {
+        let task = "[ ] add a Result as return value src:src/arg_parsing.rs:110"
+            .parse::<Task>()
+            .expect("parsing failed");
+
+        assert_eq!(
+            Task {
+                text: "add a Result as return value".to_string(),
+                status: Status::Unfinished,
+                source: Some(Source {
+                    path: "src/arg_parsing.rs".into(),
+                    line: 110
+                }),
+                ..Default::default()
+            },
+            task
+        );
+        assert_eq!(
+            "[ ] add a Result as return value src:src/arg_parsing.rs:110",
+            task.to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_and_write_dates() {
+        let date = |day| NaiveDate::from_ymd_opt(2022, 10, day).unwrap();
+        let finished = "[x] 2022-10-14 2022-10-02 Write tests"
+            .parse::<Task>()
+            .unwrap();
+        let unfinished = "[ ] 2022-10-02 2022-10-03 Write tests"
+            .parse::<Task>()
+            .unwrap();
+        let without_created = "[x] 2022-10-14 Write tests".parse::<Task>().unwrap();
+        let only_date = "[ ] 2022-10-02".parse::<Task>().unwrap();
+
+        assert_eq!(Some(date(2)), finished.created);
+        assert_eq!(Some(date(14)), finished.completed);
+        assert_eq!("Write tests", finished.text);
+        assert_eq!(Some(date(2)), unfinished.created);
+        assert_eq!(None, unfinished.completed);
+        assert_eq!("2022-10-03 Write tests", unfinished.text);
+        assert_eq!(None, without_created.created);
+        assert_eq!(Some(date(14)), without_created.completed);
+        assert_eq!(None, only_date.created);
+        assert_eq!("2022-10-02", only_date.text);
+
+        assert_eq!(
+            "[x] 2022-10-14 2022-10-02 Write tests",
+            finished.to_string()
+        );
+        assert_eq!(
+            "[ ] 2022-10-02 2022-10-03 Write tests",
+            unfinished.to_string()
+        );
+        assert_eq!("[x] 2022-10-14 Write tests", without_created.to_string());
+    }
+
+    #[test]
+    fn test_check_sets_completion_date() {
+        let date = NaiveDate::from_ymd_opt(2022, 10, 14).unwrap();
+        let mut task = "[ ] 2022-10-02 Write tests".parse::<Task>().unwrap();
+
+        task.check(date);
+
+        assert_eq!("[x] 2022-10-14 2022-10-02 Write tests", task.to_string());
+        assert_eq!("[x] Write tests", task.without_dates().to_string());
+    }
+
+    #[test]
+    fn test_check_again_keeps_completion_date() {
+        let date = NaiveDate::from_ymd_opt(2022, 10, 20).unwrap();
+        let mut task = "[x] 2022-10-14 2022-10-02 Write tests"
+            .parse::<Task>()
+            .unwrap();
+
+        task.check(date);
+
+        assert_eq!("[x] 2022-10-14 2022-10-02 Write tests", task.to_string());
+    }
+
+    #[test]
+    fn test_custom_done_status_has_completion_date() {
+        let date = NaiveDate::from_ymd_opt(2022, 10, 14).unwrap();
+        let mut markers = Markers::default();
+        markers.custom.push(CustomStatus {
+            name: "shipped".to_string(),
+            marker: 's',
+            rank: 65,
+            done: true,
+        });
+        let mut task = Task::parse_with("[ ] 2022-10-02 Release", &markers).unwrap();
+
+        task.set_status(markers.status("shipped").unwrap(), date);
+        let written = task.display_with(&markers).to_string();
+        let read = Task::parse_with(&written, &markers).unwrap();
+        task.set_status(Status::Waiting, date);
+
+        assert_eq!("[s] 2022-10-14 2022-10-02 Release", written);
+        assert_eq!(
+            (Some(date), "Release"),
+            (read.completed, read.text.as_str())
+        );
+        assert_eq!(None, task.completed);
+    }
+
+    #[test]
+    fn test_parse_due_date_and_priority() {
+        let task = "[ ] 2022-10-02 due:2022-10-20 Release pri:B due:soon"
+            .parse::<Task>()
+            .unwrap();
+
+        assert_eq!("Release due:soon", task.text);
+        assert_eq!(NaiveDate::from_ymd_opt(2022, 10, 20), task.due);
+        assert_eq!(Some('B'), task.priority);
+        assert_eq!(
+            "[ ] 2022-10-02 Release due:soon pri:B due:2022-10-20",
+            task.to_string()
+        );
+        assert_eq!(None, "[ ] pri:b".parse::<Task>().unwrap().priority);
+    }
+
+    #[test]
+    fn test_commit_is_only_read_from_a_sha() {
+        let task = "[x] Fix crash commit:3f2a9c1 id:2".parse::<Task>().unwrap();
+
+        assert_eq!(Some("3f2a9c1".to_string()), task.commit);
+        assert_eq!("[x] Fix crash id:2 commit:3f2a9c1", task.to_string());
+        assert_eq!(
+            "Read commit:message",
+            "[ ] Read commit:message".parse::<Task>().unwrap().text
+        );
+    }
+
+    #[test]
+    fn test_several_dependencies() {
+        let task = "[ ] Publish after:2 pri:A after:5 id:6"
+            .parse::<Task>()
+            .unwrap();
+
+        assert_eq!(vec![2, 5], task.after);
+        assert_eq!("[ ] Publish pri:A after:2 after:5 id:6", task.to_string());
+    }
+
+    #[test]
+    fn test_id_and_tags() {
+        let task = "[ ] Invoice +billing +acme id:7".parse::<Task>().unwrap();
+
+        assert_eq!(Some(7), task.id);
+        assert_eq!(vec!["billing", "acme"], task.tags());
+        assert_eq!("[ ] Invoice +billing +acme id:7", task.to_string());
+        assert_eq!(
+            Vec::<&str>::new(),
+            "[ ] Add 1 + 1".parse::<Task>().unwrap().tags()
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_of_recurring_task() {
+        let date = |day| NaiveDate::from_ymd_opt(2022, 10, day).unwrap();
+        let task = "[ ] 2022-10-01 Update dependencies pri:B due:2022-10-10 rec:+weekly est:30m"
+            .parse::<Task>()
+            .unwrap();
+
+        let next = task.next_occurrence(date(14)).unwrap();
+
+        assert_eq!(
+            "[ ] 2022-10-14 Update dependencies pri:B due:2022-10-17 rec:+weekly est:30m",
+            next.to_string()
+        );
+        assert_eq!(Some(date(10)), task.due);
+        assert_eq!(
+            None,
+            "[ ] Release"
+                .parse::<Task>()
+                .unwrap()
+                .next_occurrence(date(14))
+        );
+    }
+
+    #[test]
+    fn test_no_next_occurrence_out_of_range_of_dates() {
+        let date = NaiveDate::from_ymd_opt(2022, 10, 14).unwrap();
+        for rule in ["999999999d", "99999999y"] {
+            let task = format!("[ ] Renew rec:{}", rule).parse::<Task>().unwrap();
+
+            assert_eq!(None, task.next_occurrence(date), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn test_source_with_spaces_stays_one_word() {
+        let task = "[ ] fix me src:my%20dir/100%25.rs:1"
+            .parse::<Task>()
+            .unwrap();
+
+        assert_eq!(
+            Some(Source {
+                path: "my dir/100%.rs".into(),
+                line: 1
+            }),
+            task.source
+        );
+        assert_eq!("[ ] fix me src:my%20dir/100%25.rs:1", task.to_string());
+    }
+
+    #[test]
+    fn test_invalid_source_stays_in_text() {
+        let task = "[ ] Look at src:lib.rs".parse::<Task>().unwrap();
+
+        assert_eq!("Look at src:lib.rs", task.text);
+        assert_eq!(None, task.source);
+    }
+
+    #[test]
+    fn test_estimate_too_large_stays_in_text() {
+        let task = "[ ] Rewrite everything est:99999999h"
+            .parse::<Task>()
+            .unwrap();
+
+        assert_eq!("Rewrite everything est:99999999h", task.text);
+        assert_eq!(None, task.estimate);
+    }
+
+    #[test]
+    fn test_unfinished_task_to_string() {
+        let task = Task {
+            text: "Get coffee".to_string(),
+            status: Status::Unfinished,
+            ..Default::default()
+        };
+
+        assert_eq!("[ ] Get coffee", task.to_string());
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{Error, ParseError, Task};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// A stretch of time spent on the task with the given `id:`. Entries without
+/// an end are still running.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    pub id: u32,
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+}
+
+impl Entry {
+    /// Tracked time, up to `now` if the entry is still running
+    pub fn duration(&self, now: NaiveDateTime) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.id, self.start.format(TIME_FORMAT))?;
+        if let Some(end) = self.end {
+            write!(f, " {}", end.format(TIME_FORMAT))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Entry {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        let parse_time =
+            |word: &str| NaiveDateTime::parse_from_str(word, TIME_FORMAT).map_err(|_| ParseError);
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let (id, start, end) = match words[..] {
+            [id, start] => (id, parse_time(start)?, None),
+            [id, start, end] => (id, parse_time(start)?, Some(parse_time(end)?)),
+            _ => return Err(ParseError),
+        };
+        Ok(Entry {
+            id: id.parse().map_err(|_| ParseError)?,
+            start,
+            end,
+        })
+    }
+}
+
+/// What `todo report time` sums up the tracked time by
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Grouping {
+    Day,
+    Task,
+    Tag,
+}
+
+impl FromStr for Grouping {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        match input {
+            "day" => Ok(Grouping::Day),
+            "task" => Ok(Grouping::Task),
+            "tag" => Ok(Grouping::Tag),
+            _ => Err(ParseError),
+        }
+    }
+}
+
+/// `1h 05m`
+pub fn format_duration(duration: Duration) -> String {
+    format!(
+        "{}h {:02}m",
+        duration.num_hours(),
+        duration.num_minutes() % 60
+    )
+}
+
+/// The tracked time of a list, stored next to it, e.g. in `.todo.time` for
+/// `.todo`. Tasks are referred to by their `id:`, so editing or moving them
+/// keeps their time.
+#[derive(Debug, PartialEq, Default)]
+pub struct TimeLog {
+    pub entries: Vec<Entry>,
+}
+
+impl FromStr for TimeLog {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
+        let entries = input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.parse())
+            .collect::<Result<Vec<Entry>, ParseError>>()?;
+        Ok(TimeLog { entries })
+    }
+}
+
+impl fmt::Display for TimeLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in self.entries.iter() {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+impl TimeLog {
+    /// Path of the time log of the list at `list_path`
+    pub fn path(list_path: &Path) -> PathBuf {
+        list_path.with_extension("time")
+    }
+
+    /// Reads the time log of the list at `list_path`. A list without one
+    /// hasn't tracked any time yet.
+    pub fn load(list_path: &Path) -> Result<TimeLog, Error> {
+        match fs::read_to_string(TimeLog::path(list_path)) {
+            Ok(content) => content.parse().map_err(|_| Error::InvalidTimeLog),
+            Err(_) => Ok(TimeLog::default()),
+        }
+    }
+
+    pub fn save(&self, list_path: &Path) {
+        fs::write(TimeLog::path(list_path), self.to_string()).unwrap();
+    }
+
+    /// The running entry. There is at most one.
+    pub fn active(&self) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.end.is_none())
+    }
+
+    /// Starts tracking the task with `id` and returns the entry, that was
+    /// stopped for it
+    pub fn start(&mut self, id: u32, now: NaiveDateTime) -> Option<Entry> {
+        let stopped = self.stop(now);
+        self.entries.push(Entry {
+            id,
+            start: now,
+            end: None,
+        });
+        stopped
+    }
+
+    /// Stops the running entry and returns it
+    pub fn stop(&mut self, now: NaiveDateTime) -> Option<Entry> {
+        let entry = self.entries.iter_mut().find(|entry| entry.end.is_none())?;
+        entry.end = Some(now);
+        Some(entry.clone())
+    }
+
+    /// All time tracked for the task with `id`
+    pub fn tracked(&self, id: u32, now: NaiveDateTime) -> Duration {
+        self.entries
+            .iter()
+            .filter(|entry| entry.id == id)
+            .map(|entry| entry.duration(now))
+            .sum()
+    }
+
+    /// Sums up the tracked time by day, task text or tag. Entries count for
+    /// the day they started on and for every tag of their task. Time of
+    /// removed tasks is listed by their id.
+    pub fn report(
+        &self,
+        tasks: &[Task],
+        grouping: Grouping,
+        now: NaiveDateTime,
+    ) -> BTreeMap<String, Duration> {
+        let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+        for entry in self.entries.iter() {
+            let task = tasks.iter().find(|task| task.id == Some(entry.id));
+            let keys = match (grouping, task) {
+                (Grouping::Day, _) => vec![entry.start.date().to_string()],
+                (Grouping::Task, Some(task)) => vec![task.text.clone()],
+                (Grouping::Tag, Some(task)) if !task.tags().is_empty() => {
+                    task.tags().iter().map(|tag| format!("+{}", tag)).collect()
+                }
+                (Grouping::Tag, Some(_)) => vec!["(no tag)".to_string()],
+                (_, None) => vec![format!("id:{}", entry.id)],
+            };
+            for key in keys {
+                *totals.entry(key).or_insert_with(Duration::zero) += entry.duration(now);
+            }
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_time_log() {
+        let content = "1 2022-10-14T09:00:00 2022-10-14T10:30:00\n2 2022-10-14T11:00:00\n";
+        let time_log = content.parse::<TimeLog>().unwrap();
+
+        assert_eq!(Some(time(14, 10, 30)), time_log.entries[0].end);
+        assert_eq!(2, time_log.active().unwrap().id);
+        assert_eq!(content, time_log.to_string());
+        assert!("1 yesterday".parse::<TimeLog>().is_err());
+    }
+
+    #[test]
+    fn test_start_stops_running_entry() {
+        let mut time_log = TimeLog::default();
+
+        assert_eq!(None, time_log.start(1, time(14, 9, 0)));
+        let stopped = time_log.start(2, time(14, 9, 45)).unwrap();
+
+        assert_eq!(1, stopped.id);
+        assert_eq!("0h 45m", format_duration(stopped.duration(time(14, 12, 0))));
+        assert_eq!(2, time_log.active().unwrap().id);
+        assert_eq!(
+            Some(time(14, 10, 0)),
+            time_log.stop(time(14, 10, 0)).unwrap().end
+        );
+        assert_eq!(None, time_log.stop(time(14, 11, 0)));
+    }
+
+    #[test]
+    fn test_report_by_day_task_and_tag() {
+        let tasks: Vec<Task> = [
+            "[ ] Invoice +billing id:1",
+            "[x] Review +billing +acme id:2",
+        ]
+        .iter()
+        .map(|task| task.parse().unwrap())
+        .collect();
+        let time_log = TimeLog {
+            entries: vec![
+                Entry {
+                    id: 1,
+                    start: time(14, 9, 0),
+                    end: Some(time(14, 10, 30)),
+                },
+                Entry {
+                    id: 2,
+                    start: time(15, 9, 0),
+                    end: Some(time(15, 9, 20)),
+                },
+                Entry {
+                    id: 3,
+                    start: time(15, 10, 0),
+                    end: None,
+                },
+            ],
+        };
+        let now = time(15, 10, 10);
+        let report = |grouping| {
+            time_log
+                .report(&tasks, grouping, now)
+                .into_iter()
+                .map(|(key, duration)| (key, duration.num_minutes()))
+                .collect::<Vec<(String, i64)>>()
+        };
+
+        assert_eq!(
+            vec![
+                ("2022-10-14".to_string(), 90),
+                ("2022-10-15".to_string(), 30)
+            ],
+            report(Grouping::Day)
+        );
+        assert_eq!(
+            vec![
+                ("Invoice +billing".to_string(), 90),
+                ("Review +billing +acme".to_string(), 20),
+                ("id:3".to_string(), 10)
+            ],
+            report(Grouping::Task)
+        );
+        assert_eq!(
+            vec![
+                ("+acme".to_string(), 20),
+                ("+billing".to_string(), 110),
+                ("id:3".to_string(), 10)
+            ],
+            report(Grouping::Tag)
+        );
+    }
+}
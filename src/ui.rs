@@ -0,0 +1,454 @@
+use crate::command::stop_tracking;
+use crate::config::Config;
+use crate::{Error, Task, TaskList};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// What the keys currently do. The text being typed is kept in the mode.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Mode {
+    Normal,
+    Add(String),
+    Edit(String),
+    Filter,
+}
+
+/// State of `todo ui`. Every change goes through the same `TaskList` methods
+/// as the commands and is written to the file right away.
+pub struct App {
+    file_path: PathBuf,
+    task_list: TaskList,
+    modified: Option<SystemTime>,
+    today: NaiveDate,
+    /// Added items at least this similar to an open one are refused
+    similarity_threshold: f64,
+    /// Index into the visible items, not into the list
+    selected: usize,
+    pub mode: Mode,
+    pub filter: String,
+    /// Feedback for the last action, shown in the status line
+    pub message: String,
+    pub quit: bool,
+}
+
+fn modified(file_path: &Path) -> Option<SystemTime> {
+    fs::metadata(file_path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+impl App {
+    pub fn new(file_path: &Path, config: &Config, today: NaiveDate) -> Result<App, Error> {
+        let file_content = fs::read_to_string(file_path).map_err(|_| Error::NoList)?;
+        Ok(App {
+            file_path: file_path.to_path_buf(),
+            task_list: TaskList::parse_with(&file_content, &config.markers)?,
+            modified: modified(file_path),
+            today,
+            similarity_threshold: config.similarity_threshold,
+            selected: 0,
+            mode: Mode::Normal,
+            filter: String::new(),
+            message: String::new(),
+            quit: false,
+        })
+    }
+
+    /// Indices (as displayed by `todo list`) of the items matching the filter
+    fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        self.task_list
+            .display_order()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, position)| {
+                self.task_list.tasks[*position]
+                    .text
+                    .to_lowercase()
+                    .contains(&filter)
+            })
+            .map(|(index, _)| index + 1)
+            .collect()
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.visible().get(self.selected).copied()
+    }
+
+    fn selected_task(&self) -> Option<&Task> {
+        let position = self.task_list.position(self.selected_index()?).ok()?;
+        self.task_list.tasks.get(position)
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+    }
+
+    /// Reads the file again, if it was changed by someone else. A list, that
+    /// can't be read, e.g. because of a merge conflict, is shown as it was.
+    pub fn reload(&mut self) {
+        let modified = modified(&self.file_path);
+        if modified == self.modified {
+            return;
+        }
+        if let Ok(file_content) = fs::read_to_string(&self.file_path) {
+            match TaskList::parse_with(&file_content, &self.task_list.markers) {
+                Ok(task_list) => {
+                    self.task_list = task_list;
+                    self.modified = modified;
+                    self.clamp_selection();
+                    self.message = "Reloaded".to_string();
+                }
+                Err(error) => self.message = error.to_string(),
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        fs::write(&self.file_path, self.task_list.to_string_unordered()).unwrap();
+        self.modified = modified(&self.file_path);
+        self.clamp_selection();
+    }
+
+    /// Applies a change to the selected item and saves the list. Returns the
+    /// changed item.
+    fn change(
+        &mut self,
+        change: impl FnOnce(&mut TaskList, usize) -> Result<Task, Error>,
+    ) -> Option<Task> {
+        let index = self.selected_index()?;
+        match change(&mut self.task_list, index) {
+            Ok(task) => {
+                self.save();
+                self.message = self.task_list.show(&task);
+                Some(task)
+            }
+            Err(error) => {
+                self.message = error.to_string();
+                None
+            }
+        }
+    }
+
+    /// Checks the selected item and stops tracking its time, if it's tracked
+    fn check(&mut self, now: NaiveDateTime) {
+        let today = self.today;
+        let task = match self.change(|task_list, index| task_list.check(index, today)) {
+            Some(task) => task,
+            None => return,
+        };
+        let mut stopped = Vec::new();
+        match stop_tracking(&self.file_path, &self.task_list, &task, now, &mut stopped) {
+            Ok(()) if !stopped.is_empty() => {
+                self.message = String::from_utf8(stopped).unwrap().trim_end().to_string()
+            }
+            Ok(()) => {}
+            Err(error) => self.message = error.to_string(),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        self.mode = match (mode, key.code) {
+            (Mode::Normal, KeyCode::Char('q')) | (Mode::Normal, KeyCode::Esc) => {
+                self.quit = true;
+                Mode::Normal
+            }
+            (Mode::Normal, KeyCode::Up) | (Mode::Normal, KeyCode::Char('k')) => {
+                self.selected = self.selected.saturating_sub(1);
+                Mode::Normal
+            }
+            (Mode::Normal, KeyCode::Down) | (Mode::Normal, KeyCode::Char('j')) => {
+                self.selected += 1;
+                self.clamp_selection();
+                Mode::Normal
+            }
+            (Mode::Normal, KeyCode::Char(' ')) => {
+                match self.selected_task().map(|task| task.status.is_closed()) {
+                    Some(true) => {
+                        self.change(|task_list, index| task_list.uncheck(index));
+                    }
+                    Some(false) => self.check(Local::now().naive_local()),
+                    None => {}
+                }
+                Mode::Normal
+            }
+            (Mode::Normal, KeyCode::Char('d')) => {
+                self.change(|task_list, index| task_list.remove(index));
+                Mode::Normal
+            }
+            (Mode::Normal, KeyCode::Char('a')) => Mode::Add(String::new()),
+            (Mode::Normal, KeyCode::Char('e')) => match self.selected_task() {
+                Some(task) => Mode::Edit(task.text.clone()),
+                None => Mode::Normal,
+            },
+            (Mode::Normal, KeyCode::Char('/')) => Mode::Filter,
+            (Mode::Normal, _) => Mode::Normal,
+            (Mode::Add(text), KeyCode::Enter) => {
+                let duplicate = self
+                    .task_list
+                    .find_duplicate(&text, self.similarity_threshold);
+                match duplicate {
+                    _ if text.is_empty() => {}
+                    Some(duplicate) => {
+                        self.message = Error::Duplicate(Box::new(duplicate)).to_string()
+                    }
+                    None => {
+                        self.task_list.add(Task {
+                            text: text.clone(),
+                            created: Some(self.today),
+                            ..Default::default()
+                        });
+                        self.save();
+                        self.message = format!("Added: {}", text);
+                    }
+                }
+                Mode::Normal
+            }
+            (Mode::Edit(text), KeyCode::Enter) => {
+                self.change(|task_list, index| task_list.edit(index, &text));
+                Mode::Normal
+            }
+            (Mode::Filter, KeyCode::Enter) => Mode::Normal,
+            (Mode::Filter, KeyCode::Esc) => {
+                self.filter.clear();
+                Mode::Normal
+            }
+            (Mode::Filter, KeyCode::Char(c)) => {
+                self.filter.push(c);
+                self.clamp_selection();
+                Mode::Filter
+            }
+            (Mode::Filter, KeyCode::Backspace) => {
+                self.filter.pop();
+                Mode::Filter
+            }
+            (Mode::Add(_), KeyCode::Esc) | (Mode::Edit(_), KeyCode::Esc) => Mode::Normal,
+            (Mode::Add(mut text), KeyCode::Char(c)) => {
+                text.push(c);
+                Mode::Add(text)
+            }
+            (Mode::Add(mut text), KeyCode::Backspace) => {
+                text.pop();
+                Mode::Add(text)
+            }
+            (Mode::Edit(mut text), KeyCode::Char(c)) => {
+                text.push(c);
+                Mode::Edit(text)
+            }
+            (Mode::Edit(mut text), KeyCode::Backspace) => {
+                text.pop();
+                Mode::Edit(text)
+            }
+            (mode, _) => mode,
+        };
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [list_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let items: Vec<ListItem> = self
+            .visible()
+            .into_iter()
+            .map(|index| {
+                let position = self.task_list.position(index).unwrap();
+                let task = self.task_list.show(&self.task_list.tasks[position]);
+                ListItem::new(format!("{} {}", index, task))
+            })
+            .collect();
+        let title = self
+            .file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, list_area, &mut state);
+
+        let status = match &self.mode {
+            Mode::Add(text) => format!("Add: {}", text),
+            Mode::Edit(text) => format!("Edit: {}", text),
+            Mode::Filter => format!("/{}", self.filter),
+            Mode::Normal if !self.message.is_empty() => self.message.clone(),
+            Mode::Normal => {
+                "space: toggle  a: add  e: edit  d: delete  /: filter  q: quit".to_string()
+            }
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
+
+/// Runs the interface until the user quits
+pub fn run(file_path: &Path, config: &Config, today: NaiveDate) -> Result<(), Error> {
+    let mut app = App::new(file_path, config, today)?;
+    let mut terminal = ratatui::init();
+    while !app.quit {
+        app.reload();
+        terminal
+            .draw(|frame| app.draw(frame))
+            .expect("Couldn't draw to the terminal");
+        // polling with a timeout lets changes to the file show up while idle
+        if event::poll(Duration::from_millis(500)).unwrap_or(false) {
+            if let Ok(Event::Key(key)) = event::read() {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+    }
+    ratatui::restore();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assert_fs::fixture::TempDir;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, 10, 14).unwrap()
+    }
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn screen(app: &App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(40, 5)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..5)
+            .map(|y| {
+                (0..40)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keys_change_the_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n[ ] Tsak 2\n[ ] Task 3\n").unwrap();
+        let mut app = App::new(&file_path, &Config::default(), day()).unwrap();
+
+        // the checked item moves to the bottom, so `Tsak 2` is selected next
+        press(&mut app, " e");
+        for _ in 0..6 {
+            app.handle_key(KeyEvent::from(KeyCode::Backspace));
+        }
+        press(&mut app, "Task 2");
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+        press(&mut app, "jd");
+        press(&mut app, "aTask 4");
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(
+            "[x] 2022-10-14 Task 1\n[ ] Task 2\n[ ] 2022-10-14 Task 4\n",
+            fs::read_to_string(&file_path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_space_toggles() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n").unwrap();
+        let mut app = App::new(&file_path, &Config::default(), day()).unwrap();
+
+        press(&mut app, "  ");
+
+        assert_eq!("[ ] Task 1\n", fs::read_to_string(&file_path).unwrap());
+    }
+
+    #[test]
+    fn test_check_stops_tracking() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[~] Invoice id:1\n").unwrap();
+        let time_log_path = temp_dir.path().join(".todo.time");
+        fs::write(&time_log_path, "1 2022-10-14T09:00:00\n").unwrap();
+        let mut app = App::new(&file_path, &Config::default(), day()).unwrap();
+
+        app.check(day().and_hms_opt(9, 45, 0).unwrap());
+
+        assert_eq!(
+            "1 2022-10-14T09:00:00 2022-10-14T09:45:00\n",
+            fs::read_to_string(&time_log_path).unwrap()
+        );
+        assert_eq!("Stopped: Invoice (0h 45m)", app.message);
+    }
+
+    #[test]
+    fn test_draw_filtered_list() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[x] Write tests\n[ ] Fix bug\n[ ] Write docs\n").unwrap();
+        let mut app = App::new(&file_path, &Config::default(), day()).unwrap();
+
+        press(&mut app, "/write");
+
+        assert_eq!(
+            vec![
+                "┌.todo─────────────────────────────────┐",
+                "│2 [ ] Write docs                      │",
+                "│3 [x] Write tests                     │",
+                "└──────────────────────────────────────┘",
+                "/write",
+            ],
+            screen(&app)
+        );
+    }
+
+    #[test]
+    fn test_reload_when_file_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Task 1\n").unwrap();
+        let mut app = App::new(&file_path, &Config::default(), day()).unwrap();
+        app.modified = None;
+        fs::write(&file_path, "[ ] Task 1\n[ ] Task 2\n").unwrap();
+
+        app.reload();
+
+        assert_eq!(vec![1, 2], app.visible());
+        assert_eq!("Reloaded", app.message);
+    }
+
+    #[test]
+    fn test_add_refuses_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join(".todo");
+        fs::write(&file_path, "[ ] Refactor code\n").unwrap();
+        let mut app = App::new(&file_path, &Config::default(), day()).unwrap();
+
+        press(&mut app, "arefactor  Code");
+        app.handle_key(KeyEvent::from(KeyCode::Enter));
+
+        assert_eq!(
+            "[ ] Refactor code\n",
+            fs::read_to_string(&file_path).unwrap()
+        );
+        assert_eq!(
+            "A similar item is already on the list: [ ] Refactor code",
+            app.message
+        );
+        assert_eq!(Mode::Normal, app.mode);
+    }
+}
@@ -9,7 +9,7 @@ fn test_program_fails_when_no_arguments_are_passed() {
     // Mark wants to create a new todo list in the current folder
     // He doesn't know how to use the program yet and is displayed a help
     // menu, because he just typed `todo`
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.assert()
         .failure()
         .code(2)
@@ -24,7 +24,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
 
     // Mark wants to add an item to the todo list, but he hasn't created a list yet
     // the program fails and explains, that no todo list could be found
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("add")
         .arg("Test item")
         .assert()
@@ -36,7 +36,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     // Mark wants to create a new todo list in the current folder
     // He runs `todo create` to make a new todo list in the current folder
     // This creates a .todo file in the current folder
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("create");
     cmd.assert()
         .success()
@@ -45,7 +45,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
 
     // Mark uses the todo program to manage tasks for his project.
     // By running todo add "Refactor code" he adds an item to the todo list
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("add").arg("Refactor code");
     cmd.assert()
         .success()
@@ -53,7 +53,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
 
     // Immediately, he decides to view the current state of the todo list
     // by running `todo list`. Which shows him the item he added
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("list");
     cmd.assert()
         .success()
@@ -64,13 +64,13 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     // The program tells him, that there already is a .todo file present
     // Mark runs todo list again to make sure, that all of his items are still
     // there
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("create");
     cmd.assert().success().stdout(predicate::str::contains(
         "Warning: \".todo\" already exists. Quitting",
     ));
 
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("list");
     cmd.assert()
         .success()
@@ -79,7 +79,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
 
     // He wants to also add a second item to his todo list "Drink a coffe
     // with Greg"
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("add").arg("Drink a coffee with Greg");
     cmd.assert()
         .success()
@@ -88,7 +88,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     // He has another look at the todo list, which now shows him both items
     // They are numbered in the order they were added, which makes sense
     // to Mark
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("list");
     cmd.assert()
         .success()
@@ -103,7 +103,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     //
     // assert ... "[x]" in output
     // assert ... "Refactor code" in output
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("check").arg("1");
     cmd.assert()
         .success()
@@ -114,7 +114,7 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     // done by the [x] accompanying it.
     // // in color terminals it could even be a different color later on
     // Drinking coffee with Greg is now item number 1
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("list");
     cmd.assert().success().stdout(predicate::str::contains(
         "1 [ ] Drink a coffee with Greg\n\n2 [x] Refactor code",
@@ -124,14 +124,14 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     // is a bit inappropriate, when after all he wants to show his boss this
     // nice, new todo utility program, that he found.
     // He decides to remove it from the list (`todo remove 1`)
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("remove").arg("1");
     cmd.assert().success().stdout(predicate::str::contains(
         "Removed: [ ] Drink a coffee with Greg",
     ));
 
     // The todo list doesn't list the item anymore
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("list");
     cmd.assert()
         .success()
@@ -140,13 +140,469 @@ fn test_managing_list_items() -> Result<(), Box<dyn std::error::Error>> {
     // He decides, that he doesn't want the todo list after all.
     // Mark runs `todo destroy` and all list items are deleted along with the
     // .todo file
-    let mut cmd = Command::cargo_bin("todo-rs").unwrap();
+    let mut cmd = Command::cargo_bin("todo").unwrap();
     cmd.arg("destroy");
     cmd.assert()
         .success()
         .stdout(predicate::str::contains("Deleted: .todo"));
 
     assert!(!Path::new(".todo").exists());
-    
+
     Ok(())
 }
+
+#[test]
+fn test_duplicate_items_are_refused() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join(".todo"), "[ ] Refactor code\n").unwrap();
+
+    // Mark forgot, that he already added the refactoring to the list.
+    // Adding it again with slightly different spelling is refused
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("add")
+        .arg("refactor  Code")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "A similar item is already on the list: [ ] Refactor code",
+        ));
+
+    // He really wants it twice, so he forces it
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("add")
+        .arg("--force")
+        .arg("refactor  Code")
+        .assert()
+        .success();
+
+    // Later he cleans up the list again with `todo dedupe`
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("dedupe")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Merged: [ ] refactor  Code"));
+    assert_eq!(
+        "[ ] Refactor code\n",
+        std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap()
+    );
+}
+
+#[test]
+fn test_named_lists() {
+    let temp_dir = TempDir::new().unwrap();
+    let todo = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.current_dir(temp_dir.path()).args(args).assert()
+    };
+
+    // Mark keeps bugs and ideas apart from his regular tasks
+    todo(&["create"]).success();
+    todo(&["create", "--list", "bugs"])
+        .success()
+        .stdout(predicate::str::contains("created a new list \"bugs\""));
+    todo(&["create", "--list", "ideas"]).success();
+    assert!(temp_dir.path().join(".todo.d/bugs.todo").exists());
+
+    // Every subcommand works on the list given with --list
+    todo(&["add", "--list", "bugs", "Crash on empty input"]).success();
+    todo(&["--list", "bugs", "add", "Rewrite it in Rust"]).success();
+    todo(&["list", "--list", "bugs"])
+        .success()
+        .stdout(predicate::str::contains("1 [ ] Crash on empty input"));
+    todo(&["list"])
+        .success()
+        .stdout(predicate::str::contains("Crash").not());
+
+    // The rewrite isn't really a bug, so he moves it to the ideas
+    todo(&["mv", "2", "--list", "bugs", "--to", "ideas"])
+        .success()
+        .stdout(predicate::str::contains("Moved: [ ] Rewrite it in Rust"));
+    todo(&["lists"]).success().stdout(predicate::str::contains(
+        "default (0 open, 0 done)\nbugs (1 open, 0 done)\nideas (1 open, 0 done)\n",
+    ));
+}
+
+#[test]
+fn test_listing_a_directory_tree() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("crates/core")).unwrap();
+    std::fs::write(temp_dir.path().join(".todo"), "[ ] Release\n").unwrap();
+    std::fs::write(temp_dir.path().join("crates/core/.todo"), "[ ] Format\n").unwrap();
+
+    // Mark's monorepo has a list per crate. He wants to see all of them at once
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["list", "--recursive", "--flat"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            ".todo:1 [ ] Release\ncrates/core/.todo:1 [ ] Format\n",
+        ));
+
+    // He checks the item of the core crate without changing directories
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["check", "crates/core/.todo:1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[x] Format"));
+    assert_eq!(
+        "[ ] Release\n",
+        std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap()
+    );
+}
+
+#[test]
+fn test_tracking_the_status_of_items() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".todo"),
+        "[ ] Fix crash\n[ ] Release notes\n[ ] Feedback from Anna\n[*] From another tool\n",
+    )
+    .unwrap();
+    let todo = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.current_dir(temp_dir.path()).args(args).assert()
+    };
+
+    // Mark starts on the release notes and is waiting for Anna's feedback
+    todo(&["start", "2"]).success();
+    todo(&["wait", "3"])
+        .success()
+        .stdout(predicate::str::contains("[?] Feedback from Anna"));
+    todo(&["list"]).success().stdout(predicate::str::contains(
+        "1 [~] Release notes id:1\n\n2 [ ] Fix crash\n\n\
+         3 [?] Feedback from Anna\n\n4 [*] From another tool\n",
+    ));
+
+    // The crash turns out to be a won't-fix, then it comes back
+    todo(&["cancel", "2"])
+        .success()
+        .stdout(predicate::str::contains("[-] Fix crash"));
+    todo(&["stats"]).success().stdout(predicate::str::contains(
+        "Open: 3\nDone: 0 (0%)\nCancelled: 1\n",
+    ));
+    todo(&["reopen", "4"])
+        .success()
+        .stdout(predicate::str::contains("[ ] Fix crash"));
+    assert_eq!(
+        "[ ] Fix crash\n[~] Release notes id:1\n[?] Feedback from Anna\n[*] From another tool\n",
+        std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap()
+    );
+}
+
+#[test]
+fn test_project_specific_markers() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".todo.toml"),
+        "[statuses]\ndone = \"✓\"\n[statuses.review]\nmarker = \"r\"\nrank = 15\n",
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join(".todo"),
+        "[X] Write tests\n[ ] Fix crash\n[R] New parser\n",
+    )
+    .unwrap();
+    let todo = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.current_dir(temp_dir.path()).args(args).assert()
+    };
+
+    // Mark's team ticks items off with a check mark and reviews them first
+    todo(&["list"]).success().stdout(predicate::str::contains(
+        "1 [r] New parser\n\n2 [ ] Fix crash\n\n3 [✓] Write tests\n",
+    ));
+    todo(&["check", "2"])
+        .success()
+        .stdout(predicate::str::contains("[✓] Fix crash"));
+    let content = std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap();
+    assert!(content.starts_with("[✓] Write tests\n[✓] "));
+    assert!(content.ends_with(" Fix crash\n[r] New parser\n"));
+}
+
+#[test]
+fn test_board_during_stand_up() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".todo"),
+        "[ ] Fix crash\n[~] Release notes\n[?] Feedback from Anna\n",
+    )
+    .unwrap();
+    let todo = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.current_dir(temp_dir.path()).args(args).assert()
+    };
+
+    // In the stand-up, Mark's team goes through the board column by column
+    todo(&["board", "--width", "72"])
+        .success()
+        .stdout(predicate::str::contains(
+            "in-progress (1) │ open (1)        │ waiting (1)     │ done (0)\n",
+        ))
+        .stdout(predicate::str::contains(
+            "1 Release notes │ 2 Fix crash     │ 3 Feedback from │\n",
+        ));
+
+    // Anna's feedback came in, so that item can be worked on again
+    todo(&["move-status", "3", "in-progress"])
+        .success()
+        .stdout(predicate::str::contains("[~] Feedback from Anna"));
+    todo(&["move-status", "1", "reviewed"])
+        .failure()
+        .stderr(predicate::str::contains(
+            "Error: There is no status called \"reviewed\"",
+        ));
+    todo(&["board", "--width", "72"])
+        .success()
+        .stdout(predicate::str::contains("in-progress (2)"))
+        .stdout(predicate::str::contains("2 Feedback from Anna"));
+
+    // The minutes should go to the notes folder, which nobody created yet
+    todo(&[
+        "export",
+        "--format",
+        "markdown",
+        "--output",
+        "notes/board.md",
+    ])
+    .failure()
+    .stderr(predicate::str::contains(
+        "Error: Couldn't create notes/board.md",
+    ));
+}
+
+#[test]
+fn test_planning_the_day() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".todo"),
+        "[ ] Docs +web est:2h\n[ ] Fix crash pri:A est:3h id:1\n[ ] Invoice due:2022-10-20 est:1h\n",
+    )
+    .unwrap();
+    let todo = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.current_dir(temp_dir.path()).args(args).assert()
+    };
+
+    // Mark has six hours today and lets todo pick what fits
+    todo(&["list", "--estimates"])
+        .success()
+        .stdout(predicate::str::contains(
+            "Estimates by status:\n6h 00m\topen\n",
+        ));
+    todo(&["plan", "--capacity", "5h"])
+        .success()
+        .stdout(predicate::str::contains(
+            "2 [ ] Fix crash pri:A est:3h id:1\n3 [ ] Invoice due:2022-10-20 est:1h\n\
+             Planned: 4h 00m of 5h 00m\n",
+        ));
+    todo(&["plan", "--capacity", "a while"])
+        .failure()
+        .stderr(predicate::str::contains("--capacity"));
+
+    // Fixing the crash took longer than he thought
+    std::fs::write(
+        temp_dir.path().join(".todo.time"),
+        "1 2022-10-14T09:00:00 2022-10-14T13:00:00\n",
+    )
+    .unwrap();
+    todo(&["check", "2"]).success();
+    todo(&["report", "estimates"])
+        .success()
+        .stdout(predicate::str::contains(
+            "3h 00m\t4h 00m\t133%\tFix crash\n",
+        ));
+}
+
+#[test]
+fn test_grooming_the_list_in_the_shell() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(temp_dir.path().join(".todo"), "[ ] Release\n").unwrap();
+
+    // Mark grooms his backlog in one go, without starting `todo` for every command
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("shell")
+        .write_stdin("add \"Write tests\"\ntop 2\ncheck 2\nfrobnicate\nlist\nexit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Added: Write tests\nMoved: [ ] Write tests\n[x] Release\n",
+        ))
+        .stdout(predicate::str::contains(
+            "1 [ ] Write tests\n\n2 [x] Release\n",
+        ))
+        .stderr(predicate::str::contains("frobnicate"));
+
+    // When he leaves the shell, the list is saved
+    let content = std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap();
+    assert!(content.ends_with(" Release\n"));
+    assert!(content.starts_with("[ ] "));
+}
+
+#[test]
+fn test_linking_items_to_commits() {
+    let temp_dir = TempDir::new().unwrap();
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap()
+    };
+    let git_ok = |args: &[&str]| {
+        let output = git(args);
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+    git_ok(&["init", "--quiet"]);
+    git_ok(&["config", "user.name", "Mark"]);
+    git_ok(&["config", "user.email", "mark@example.com"]);
+    std::fs::write(
+        temp_dir.path().join(".todo"),
+        "[ ] Fix crash\n[ ] Release\n[ ] Write docs\n",
+    )
+    .unwrap();
+    git_ok(&["add", ".todo"]);
+    git_ok(&["commit", "--quiet", "-m", "Add list"]);
+
+    // Mark fixed the crash in the commit he just made and links it to the item
+    let head = git_ok(&["rev-parse", "HEAD"]);
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["check", "1", "--commit"])
+        .assert()
+        .success();
+    let content = std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap();
+    assert!(content.contains(&format!(" Fix crash commit:{}\n", head)));
+
+    // From now on, he wants his commit messages to check the items they finish
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["git-hook", "install"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Installed: "));
+    git(&[
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "-m",
+        "Ship it\n\nTodo: #1",
+    ]);
+
+    let content = std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap();
+    assert!(content.contains(" Release id:1\n"));
+    assert!(content.starts_with("[x] "));
+    assert_eq!(
+        "Ship it\n\nTodo: id:1",
+        git_ok(&["log", "-1", "--format=%B"])
+    );
+
+    // A commit referring to an item, that isn't on the list, gets a warning
+    let output = git(&["commit", "--allow-empty", "-m", "Typo\n\nTodo: #7"]);
+    assert!(output.status.success());
+    let hook_output = [output.stdout, output.stderr].concat();
+    assert!(String::from_utf8_lossy(&hook_output)
+        .contains("Warning: #7: There is no item with this index"));
+}
+
+#[test]
+fn test_merging_branches_that_changed_the_list() {
+    let temp_dir = TempDir::new().unwrap();
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(temp_dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    };
+    let todo = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("todo").unwrap();
+        cmd.current_dir(temp_dir.path())
+            .args(args)
+            .assert()
+            .success();
+    };
+    git(&["init", "--quiet", "--initial-branch=main"]);
+    git(&["config", "user.name", "Mark"]);
+    git(&["config", "user.email", "mark@example.com"]);
+    std::fs::write(
+        temp_dir.path().join(".todo"),
+        "[ ] Fix crash\n[ ] Release\n",
+    )
+    .unwrap();
+
+    // Mark registers the merge driver, so that branches don't conflict on the list
+    todo(&["git-setup"]);
+    assert!(
+        std::fs::read_to_string(temp_dir.path().join(".gitattributes"))
+            .unwrap()
+            .contains(".todo merge=todo\n")
+    );
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "Add list"]);
+
+    // On a branch he fixes the crash and plans a test, while another item is added on main
+    git(&["checkout", "--quiet", "-b", "fix"]);
+    todo(&["check", "1"]);
+    todo(&["add", "Add a regression test"]);
+    git(&["commit", "--quiet", "-am", "Fix crash"]);
+    git(&["checkout", "--quiet", "main"]);
+    todo(&["add", "Write docs"]);
+    git(&["commit", "--quiet", "-am", "Plan docs"]);
+
+    // Merging keeps both changes without a conflict
+    git(&["merge", "--quiet", "--no-edit", "fix"]);
+    let content = std::fs::read_to_string(temp_dir.path().join(".todo")).unwrap();
+    assert!(content.starts_with("[x] "));
+    assert!(content.contains(" Fix crash\n[ ] Release\n[ ] "));
+    assert!(content.contains(" Add a regression test\n[ ] "));
+    assert!(content.ends_with(" Write docs\n"));
+
+    // For his review, he looks at what the merge did to the list on main
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["diff", "HEAD^..HEAD"])
+        .assert()
+        .success()
+        .stdout("Added:\n  [ ] Add a regression test\nCompleted:\n  [x] Fix crash\n");
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("diff")
+        .assert()
+        .success()
+        .stdout("No changes\n");
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .args(["diff", "nope"])
+        .assert()
+        .failure()
+        .stderr("Error: Unknown revision: nope\n");
+
+    // Checking an item on one side and cancelling it on the other conflicts, and the
+    // list has to be resolved before it can be used again
+    git(&["checkout", "--quiet", "-b", "release"]);
+    todo(&["check", "1"]);
+    git(&["commit", "--quiet", "-am", "Release"]);
+    git(&["checkout", "--quiet", "main"]);
+    todo(&["cancel", "1"]);
+    git(&["commit", "--quiet", "-am", "Skip the release"]);
+    let output = std::process::Command::new("git")
+        .args(["merge", "--quiet", "--no-edit", "release"])
+        .current_dir(temp_dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let mut cmd = Command::cargo_bin("todo").unwrap();
+    cmd.current_dir(temp_dir.path())
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr("Error: The list has unresolved merge conflicts\n");
+}